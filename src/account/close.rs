@@ -0,0 +1,271 @@
+use crate::asset::{find_balance, format_resource_asset, parse_resource_asset};
+use crate::config::AppConfig;
+use crate::error::Error;
+use crate::fee;
+use crate::pay::{apply_slippage, filter_paths, parse_slippage, stroops};
+use crate::signer::parse_signer_key;
+use crate::tx::explain::explain_envelope;
+use crate::wallet::{self, MemoOptions, TransactionOptions};
+use anyhow::Result;
+use convey::Output;
+use std::str::FromStr;
+use stellar_base::amount::{Amount, Price, Stroops};
+use stellar_base::signature::Signer;
+use stellar_base::xdr::MAX_OPS_PER_TX;
+use stellar_base::{Asset, Operation, PublicKey};
+use stellar_horizon::api;
+use stellar_horizon::client::HorizonClient;
+use stellar_horizon::request::PageRequest;
+use stellar_horizon::resources;
+use structopt::StructOpt;
+
+const OFFERS_PAGE_LIMIT: u64 = 200;
+
+#[derive(Debug, StructOpt)]
+pub struct CloseCommand {
+    #[structopt(short, long, help = "Which Horizon server to use")]
+    pub server: Option<String>,
+    #[structopt(
+        name = "ACCOUNT_ID",
+        help = "The account to close, starts with G, or its name"
+    )]
+    pub account_id: String,
+    #[structopt(
+        long,
+        help = "The account receiving the XLM and the assets it trusts, or its name"
+    )]
+    pub into: String,
+    #[structopt(
+        long,
+        default_value = "1",
+        help = "The slippage tolerance in percent applied when selling assets for XLM"
+    )]
+    pub slippage: String,
+    #[structopt(short, long, help = "Close the account without asking")]
+    pub yes: bool,
+    #[structopt(
+        long,
+        help = "Send even if the destination requires a memo and there is none"
+    )]
+    pub skip_memo_check: bool,
+    #[structopt(flatten)]
+    pub memo: MemoOptions,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+pub async fn run_close(
+    mut out: &mut Output,
+    config: &AppConfig,
+    command: CloseCommand,
+) -> Result<()> {
+    let client = wallet::horizon_client(&config, &command.server)?;
+    let keypair = config.find_account(&command.account_id)?.keypair()?;
+    let source = keypair.public_key();
+    let destination = config.resolve_public_key(&command.into)?;
+    if destination == *source {
+        return Err(anyhow!("Cannot close an account into itself"));
+    }
+    let account = wallet::load_account(&client, source).await?;
+    let receiver = wallet::load_account_if_exists(&client, &destination)
+        .await?
+        .ok_or_else(|| anyhow!("Destination {} does not exist", destination))?;
+    if account.num_sponsoring > 0 {
+        return Err(anyhow!(
+            "Account {} sponsors {} reserves, revoke the sponsorships first",
+            command.account_id,
+            account.num_sponsoring
+        ));
+    }
+    let slippage = parse_slippage(&command.slippage)?;
+
+    // Offers go first, so that their liabilities do not lock any balance.
+    let mut operations = Vec::new();
+    for offer in account_offers(&client, source).await? {
+        operations.push(
+            Operation::new_manage_sell_offer()
+                .with_selling_asset(parse_resource_asset(&offer.selling)?)
+                .with_buying_asset(parse_resource_asset(&offer.buying)?)
+                .with_amount(Amount::from_str("0")?)?
+                .with_price(Price::new(
+                    offer.price_ratio.numerator,
+                    offer.price_ratio.denominator,
+                ))
+                .with_offer_id(Some(offer.id))
+                .build()?,
+        );
+    }
+    for balance in &account.balances {
+        match balance.asset.asset_type.as_str() {
+            "native" => continue,
+            "credit_alphanum4" | "credit_alphanum12" => {}
+            _ => {
+                return Err(anyhow!(
+                    "Account {} has liquidity pool shares, withdraw them first",
+                    command.account_id
+                ))
+            }
+        }
+        let asset = parse_resource_asset(&balance.asset)?;
+        if stroops(&balance.balance)? > 0 {
+            operations.push(
+                move_balance(&client, source, &destination, &receiver, balance, slippage).await?,
+            );
+        }
+        operations.push(
+            Operation::new_change_trust()
+                .with_asset(asset)
+                .with_limit::<Stroops>(None)?
+                .build()?,
+        );
+    }
+    for name in account.data.keys() {
+        operations.push(
+            Operation::new_manage_data()
+                .with_data_name(name.clone())
+                .with_data_value(None)
+                .build()?,
+        );
+    }
+    for signer in &account.signers {
+        if signer.key != account.account_id {
+            let key = parse_signer_key(&signer.key)?;
+            operations.push(
+                Operation::new_set_options()
+                    .with_signer(Some(Signer::new(key, 0)))
+                    .build()?,
+            );
+        }
+    }
+    operations.push(
+        Operation::new_account_merge()
+            .with_destination(destination.clone().into())
+            .build()?,
+    );
+
+    let sequence = wallet::next_sequence(&account)?;
+    let memo = command.memo.to_memo()?;
+    let mut transactions = Vec::new();
+    for (index, chunk) in operations.chunks(MAX_OPS_PER_TX as usize).enumerate() {
+        let mut tx = wallet::build_transaction_with_sequence(
+            source,
+            sequence + index as i64,
+            chunk.to_vec(),
+        )?;
+        *tx.memo_mut() = memo.clone();
+        if !command.skip_memo_check {
+            wallet::check_memo_required(&client, &tx).await?;
+        }
+        fee::apply_fee_strategy(out, &client, &command.transaction, &mut tx).await?;
+        transactions.push(tx);
+    }
+
+    for tx in &transactions {
+        let explanation = explain_envelope(config, &tx.clone().into_envelope());
+        out.print(explanation).map_err(Error::Convey)?;
+    }
+    out.flush().map_err(Error::Convey)?;
+    if !command.yes && !wallet::confirm("Close the account?")? {
+        return Err(anyhow!("Account not closed"));
+    }
+    let network = wallet::network(&client).await?;
+    for mut tx in transactions {
+        tx.sign(&keypair, &network)?;
+        wallet::submit_envelope(&mut out, &client, &command.transaction, &tx.into_envelope())
+            .await?;
+    }
+    Ok(())
+}
+
+/// Returns the operation moving a balance out of the account: a payment if
+/// the destination trusts the asset, or else a path payment selling it for
+/// XLM.
+async fn move_balance<H>(
+    client: &H,
+    source: &PublicKey,
+    destination: &PublicKey,
+    receiver: &resources::Account,
+    balance: &resources::Balance,
+    slippage: i128,
+) -> Result<Operation>
+where
+    H: HorizonClient,
+{
+    let asset = parse_resource_asset(&balance.asset)?;
+    let amount = Amount::from_str(&balance.balance)?;
+    if balance.is_authorized == Some(false) {
+        return Err(anyhow!(
+            "Cannot move {} {}, the trustline is not authorized",
+            balance.balance,
+            format_resource_asset(&balance.asset)
+        ));
+    }
+    if let Some(trustline) = find_balance(receiver, &asset) {
+        let fits = match &trustline.limit {
+            Some(limit) => {
+                stroops(limit)? - stroops(&trustline.balance)? >= stroops(&balance.balance)?
+            }
+            None => true,
+        };
+        if trustline.is_authorized != Some(false) && fits {
+            return Ok(Operation::new_payment()
+                .with_destination(destination.clone())
+                .with_amount(amount)?
+                .with_asset(asset)
+                .build()?);
+        }
+    }
+
+    // Selling through a path payment to itself fills at once, unlike an offer.
+    let request = api::aggregations::paths_strict_send(asset.clone(), vec![], amount.clone())?
+        .with_destination_account(source);
+    let (_, response) = client.request(request).await?;
+    let mut paths = filter_paths(response.records, &Asset::new_native(), |p| {
+        &p.destination_asset
+    });
+    paths.sort_by_key(|p| std::cmp::Reverse(stroops(&p.destination_amount).unwrap_or(0)));
+    let path = paths.into_iter().next().ok_or_else(|| {
+        anyhow!(
+            "No path to sell {} {} for XLM and the destination does not trust it",
+            balance.balance,
+            format_resource_asset(&balance.asset)
+        )
+    })?;
+    let destination_min = apply_slippage(&path.destination_amount, slippage, false)?;
+    let mut builder = Operation::new_path_payment_strict_send()
+        .with_destination(source.clone())
+        .with_send_asset(asset)
+        .with_send_amount(amount)?
+        .with_destination_asset(Asset::new_native())
+        .with_destination_min(destination_min)?;
+    for asset in &path.path {
+        builder = builder.add_asset(parse_resource_asset(asset)?);
+    }
+    Ok(builder.build()?)
+}
+
+async fn account_offers<H>(client: &H, account: &PublicKey) -> Result<Vec<resources::Offer>>
+where
+    H: HorizonClient,
+{
+    let mut offers = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut request = api::offers::for_account(account).with_limit(OFFERS_PAGE_LIMIT);
+        if let Some(cursor) = &cursor {
+            request = request.with_cursor(cursor);
+        }
+        let (_, response) = client.request(request).await?;
+        let last = match response.records.last() {
+            None => break,
+            Some(last) => last.paging_token.clone(),
+        };
+        let done = (response.records.len() as u64) < OFFERS_PAGE_LIMIT;
+        offers.extend(response.records);
+        if done {
+            break;
+        }
+        cursor = Some(last);
+    }
+    Ok(offers)
+}
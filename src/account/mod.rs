@@ -5,6 +5,10 @@ use convey::Output;
 use stellar_base::KeyPair;
 use structopt::StructOpt;
 
+mod close;
+mod require_memo;
+mod set_options;
+
 #[derive(Debug, StructOpt)]
 #[structopt(about = "Manage Stellar accounts")]
 pub enum AccountCommand {
@@ -16,6 +20,12 @@ pub enum AccountCommand {
     Remove(RemoveCommand),
     #[structopt(about = "Lists all accounts")]
     List,
+    #[structopt(about = "Changes the signers, thresholds, flags and home domain of an account")]
+    SetOptions(set_options::SetOptionsCommand),
+    #[structopt(about = "Empties an account and merges it into another")]
+    Close(close::CloseCommand),
+    #[structopt(about = "Requires or stops requiring a memo on payments to an account")]
+    RequireMemo(require_memo::RequireMemoCommand),
 }
 
 #[derive(Debug, StructOpt)]
@@ -30,7 +40,7 @@ pub struct RemoveCommand {
     account_id: String,
 }
 
-pub fn run_command(
+pub async fn run_command(
     mut out: &mut Output,
     mut config: &mut AppConfig,
     command: AccountCommand,
@@ -40,6 +50,13 @@ pub fn run_command(
         AccountCommand::Add(cmd) => run_add(&mut out, &mut config, cmd),
         AccountCommand::Remove(cmd) => run_remove(&mut out, &mut config, cmd),
         AccountCommand::List => run_list(&mut out, &config),
+        AccountCommand::SetOptions(cmd) => {
+            set_options::run_set_options(&mut out, &config, cmd).await
+        }
+        AccountCommand::Close(cmd) => close::run_close(&mut out, &config, cmd).await,
+        AccountCommand::RequireMemo(cmd) => {
+            require_memo::run_require_memo(&mut out, &config, cmd).await
+        }
     }
 }
 
@@ -0,0 +1,57 @@
+use crate::config::AppConfig;
+use crate::wallet::{self, TransactionOptions, MEMO_REQUIRED_DATA_NAME};
+use anyhow::Result;
+use convey::Output;
+use stellar_base::account::DataValue;
+use stellar_base::Operation;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct RequireMemoCommand {
+    #[structopt(short, long, help = "Which Horizon server to use")]
+    pub server: Option<String>,
+    #[structopt(
+        name = "ACCOUNT_ID",
+        help = "The account id, starts with G, or its name"
+    )]
+    pub account_id: String,
+    #[structopt(
+        name = "STATE",
+        possible_values = &["on", "off"],
+        help = "Whether payments to the account must have a memo"
+    )]
+    pub state: String,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+pub async fn run_require_memo(
+    mut out: &mut Output,
+    config: &AppConfig,
+    command: RequireMemoCommand,
+) -> Result<()> {
+    let client = wallet::horizon_client(&config, &command.server)?;
+    let keypair = config.find_account(&command.account_id)?.keypair()?;
+    let account = wallet::load_account(&client, keypair.public_key()).await?;
+    let required = command.state == "on";
+    if wallet::requires_memo(&account) == required {
+        return Err(anyhow!(
+            "Memos are already {} for {}",
+            if required { "required" } else { "optional" },
+            command.account_id
+        ));
+    }
+
+    // SEP-29 uses the value 1, removing the entry makes memos optional.
+    let value = if required {
+        Some(DataValue::from_slice(b"1")?)
+    } else {
+        None
+    };
+    let operation = Operation::new_manage_data()
+        .with_data_name(MEMO_REQUIRED_DATA_NAME.to_string())
+        .with_data_value(value)
+        .build()?;
+    let tx = wallet::build_transaction(&client, keypair.public_key(), vec![operation]).await?;
+    wallet::sign_and_submit(&mut out, &client, &command.transaction, tx, &[keypair]).await
+}
@@ -0,0 +1,335 @@
+use crate::config::AppConfig;
+use crate::error::Error;
+use crate::signer::{format_signer_key, parse_signer_key};
+use crate::wallet::{self, TransactionOptions};
+use anyhow::Result;
+use convey::components::{newline, text};
+use convey::{Output, Render};
+use stellar_base::account::AccountFlags;
+use stellar_base::signature::{Signer, SignerKey};
+use stellar_base::Operation;
+use stellar_horizon::resources;
+use structopt::StructOpt;
+
+const FLAGS: &[&str] = &["auth_required", "auth_revocable", "auth_immutable"];
+
+#[derive(Debug, StructOpt)]
+pub struct SetOptionsCommand {
+    #[structopt(short, long, help = "Which Horizon server to use")]
+    pub server: Option<String>,
+    #[structopt(
+        name = "ACCOUNT_ID",
+        help = "The account id, starts with G, or its name"
+    )]
+    pub account_id: String,
+    #[structopt(
+        long,
+        number_of_values = 1,
+        help = "Add or update a signer as KEY:WEIGHT, can be repeated"
+    )]
+    pub add_signer: Vec<String>,
+    #[structopt(long, number_of_values = 1, help = "Remove a signer, can be repeated")]
+    pub remove_signer: Vec<String>,
+    #[structopt(long, help = "The weight of the account master key")]
+    pub master_weight: Option<u8>,
+    #[structopt(long, help = "The low threshold")]
+    pub low_threshold: Option<u8>,
+    #[structopt(long, help = "The medium threshold")]
+    pub med_threshold: Option<u8>,
+    #[structopt(long, help = "The high threshold")]
+    pub high_threshold: Option<u8>,
+    #[structopt(long, help = "The account home domain")]
+    pub home_domain: Option<String>,
+    #[structopt(
+        long,
+        number_of_values = 1,
+        possible_values = FLAGS,
+        help = "Set an account flag, can be repeated"
+    )]
+    pub set_flag: Vec<String>,
+    #[structopt(
+        long,
+        number_of_values = 1,
+        possible_values = FLAGS,
+        help = "Clear an account flag, can be repeated"
+    )]
+    pub clear_flag: Vec<String>,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct AccountOptions {
+    signers: Vec<(String, u32)>,
+    low_threshold: u32,
+    medium_threshold: u32,
+    high_threshold: u32,
+    home_domain: Option<String>,
+    flags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OptionChange {
+    pub option: String,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OptionChangeList {
+    pub options: Vec<OptionChange>,
+}
+
+pub async fn run_set_options(
+    mut out: &mut Output,
+    config: &AppConfig,
+    command: SetOptionsCommand,
+) -> Result<()> {
+    let client = wallet::horizon_client(&config, &command.server)?;
+    let keypair = config.find_account(&command.account_id)?.keypair()?;
+    let account_id = keypair.public_key().account_id();
+    let account = wallet::load_account(&client, keypair.public_key()).await?;
+
+    let mut signers = Vec::new();
+    for signer in &command.add_signer {
+        let mut parts = signer.splitn(2, ':');
+        let key = parse_signer_key(parts.next().unwrap_or_default())?;
+        let weight: u8 = parts
+            .next()
+            .ok_or_else(|| anyhow!("Invalid signer {}, use KEY:WEIGHT", signer))?
+            .parse()?;
+        signers.push(Signer::new(key, weight.into()));
+    }
+    for signer in &command.remove_signer {
+        if *signer == account_id {
+            return Err(anyhow!("Use --master-weight 0 to remove the master key"));
+        }
+        signers.push(Signer::new(parse_signer_key(signer)?, 0));
+    }
+    let set_flags = parse_flags(&command.set_flag);
+    let clear_flags = parse_flags(&command.clear_flag);
+
+    let before = AccountOptions::new(&account);
+    let mut after = before.clone();
+    if let Some(weight) = command.master_weight {
+        after.set_signer_weight(&account_id, weight.into());
+    }
+    for signer in &signers {
+        let key = format_signer_key(signer.key());
+        if signer.weight() == &0 && !after.signers.iter().any(|(k, _)| *k == key) {
+            return Err(anyhow!("Signer {} not found", key));
+        }
+        after.set_signer_weight(&key, *signer.weight());
+    }
+    if let Some(threshold) = command.low_threshold {
+        after.low_threshold = threshold.into();
+    }
+    if let Some(threshold) = command.med_threshold {
+        after.medium_threshold = threshold.into();
+    }
+    if let Some(threshold) = command.high_threshold {
+        after.high_threshold = threshold.into();
+    }
+    if let Some(home_domain) = &command.home_domain {
+        after.home_domain = Some(home_domain.clone());
+    }
+    after
+        .flags
+        .retain(|flag| !command.clear_flag.contains(flag));
+    for flag in &command.set_flag {
+        if !after.flags.contains(flag) {
+            after.flags.push(flag.clone());
+        }
+    }
+    if before == after {
+        return Err(anyhow!("No account options to change"));
+    }
+
+    out.print(before.changes(&after)).map_err(Error::Convey)?;
+    after.check_not_locked_out()?;
+
+    let mut operations = Vec::new();
+    let mut builder = Operation::new_set_options()
+        .with_master_weight(command.master_weight.map(u32::from))
+        .with_low_threshold(command.low_threshold.map(u32::from))
+        .with_medium_threshold(command.med_threshold.map(u32::from))
+        .with_high_threshold(command.high_threshold.map(u32::from))
+        .with_set_flags(set_flags)
+        .with_clear_flags(clear_flags);
+    // Each set options operation can change a single signer.
+    let mut signers = signers.into_iter();
+    if let Some(signer) = signers.next() {
+        builder = builder.with_signer(Some(signer));
+    }
+    let mut operation = builder.build()?;
+    if let Operation::SetOptions(ref mut op) = operation {
+        *op.home_domain_mut() = command.home_domain.clone();
+    }
+    operations.push(operation);
+    for signer in signers {
+        operations.push(
+            Operation::new_set_options()
+                .with_signer(Some(signer))
+                .build()?,
+        );
+    }
+    let tx = wallet::build_transaction(&client, keypair.public_key(), operations).await?;
+    wallet::sign_and_submit(&mut out, &client, &command.transaction, tx, &[keypair]).await
+}
+
+fn parse_flags(flags: &[String]) -> Option<AccountFlags> {
+    let mut result = AccountFlags::empty();
+    for flag in flags {
+        result |= match flag.as_str() {
+            "auth_required" => AccountFlags::AUTH_REQUIRED,
+            "auth_revocable" => AccountFlags::AUTH_REVOCABLE,
+            _ => AccountFlags::AUTH_IMMUTABLE,
+        };
+    }
+    if result.is_empty() {
+        None
+    } else {
+        Some(result)
+    }
+}
+
+impl AccountOptions {
+    fn new(account: &resources::Account) -> AccountOptions {
+        let signers = account
+            .signers
+            .iter()
+            .map(|s| (s.key.clone(), s.weight as u32))
+            .collect();
+        let flags = [
+            account.flags.auth_required,
+            account.flags.auth_revocable,
+            account.flags.auth_immutable,
+        ];
+        let flags = FLAGS
+            .iter()
+            .zip(flags.iter())
+            .filter(|(_, set)| **set)
+            .map(|(flag, _)| flag.to_string())
+            .collect();
+        AccountOptions {
+            signers,
+            low_threshold: account.thresholds.low_threshold.into(),
+            medium_threshold: account.thresholds.medium_threshold.into(),
+            high_threshold: account.thresholds.high_threshold.into(),
+            home_domain: account.home_domain.clone().filter(|d| !d.is_empty()),
+            flags,
+        }
+    }
+
+    fn set_signer_weight(&mut self, key: &str, weight: u32) {
+        match self.signers.iter_mut().find(|(k, _)| k == key) {
+            Some(signer) => signer.1 = weight,
+            None => self.signers.push((key.to_string(), weight)),
+        }
+    }
+
+    fn signer_weight(&self, key: &str) -> Option<u32> {
+        self.signers
+            .iter()
+            .find(|(k, w)| k == key && *w > 0)
+            .map(|(_, w)| *w)
+    }
+
+    /// Refuses options where the signers that can still sign, that is all
+    /// but the single-use pre authorized transactions, cannot reach the high
+    /// threshold, since the account could not be changed anymore.
+    fn check_not_locked_out(&self) -> Result<()> {
+        let weight: u32 = self
+            .signers
+            .iter()
+            .filter(|(key, _)| !matches!(parse_signer_key(key), Ok(SignerKey::PreAuthTx(_))))
+            .map(|(_, weight)| weight)
+            .sum();
+        if weight == 0 || weight < self.high_threshold {
+            return Err(anyhow!(
+                "Refusing to lock the account out: the signers weight {} is below the high threshold {}",
+                weight,
+                self.high_threshold
+            ));
+        }
+        Ok(())
+    }
+
+    fn changes(&self, after: &AccountOptions) -> OptionChangeList {
+        let mut options = Vec::new();
+        let mut keys: Vec<&String> = self.signers.iter().map(|(k, _)| k).collect();
+        for (key, _) in &after.signers {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        for key in keys {
+            options.push(OptionChange::new(
+                &format!("signer {}", key),
+                self.signer_weight(key),
+                after.signer_weight(key),
+            ));
+        }
+        options.push(OptionChange::new(
+            "low threshold",
+            Some(self.low_threshold),
+            Some(after.low_threshold),
+        ));
+        options.push(OptionChange::new(
+            "medium threshold",
+            Some(self.medium_threshold),
+            Some(after.medium_threshold),
+        ));
+        options.push(OptionChange::new(
+            "high threshold",
+            Some(self.high_threshold),
+            Some(after.high_threshold),
+        ));
+        options.push(OptionChange::new(
+            "home domain",
+            self.home_domain.as_ref(),
+            after.home_domain.as_ref(),
+        ));
+        options.push(OptionChange::new(
+            "flags",
+            Some(self.flags.join(",")).filter(|f| !f.is_empty()),
+            Some(after.flags.join(",")).filter(|f| !f.is_empty()),
+        ));
+        OptionChangeList { options }
+    }
+}
+
+impl OptionChange {
+    fn new<T: ToString>(option: &str, before: Option<T>, after: Option<T>) -> OptionChange {
+        let format = |value: Option<T>| value.map_or_else(|| "-".to_string(), |v| v.to_string());
+        OptionChange {
+            option: option.to_string(),
+            before: format(before),
+            after: format(after),
+        }
+    }
+}
+
+impl Render for OptionChange {
+    render_for_humans!(self -> [
+        text(&self.option), text("\t"),
+        text(&self.before), text("\t"),
+        text(&self.after), newline(),
+    ]);
+
+    render_json!();
+}
+
+impl Render for OptionChangeList {
+    fn render_for_humans(
+        &self,
+        mut fmt: &mut convey::human::Formatter,
+    ) -> std::result::Result<(), convey::Error> {
+        for option in &self.options {
+            option.render_for_humans(&mut fmt)?;
+        }
+        Ok(())
+    }
+
+    render_json!();
+}
@@ -0,0 +1,110 @@
+use crate::auth;
+use crate::config::AppConfig;
+use crate::stellar_toml::{self, StellarToml};
+use crate::wallet;
+use anyhow::Result;
+use convey::Output;
+use stellar_base::KeyPair;
+use structopt::StructOpt;
+
+mod quote;
+mod sep6;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Move funds in and out of the network through anchors")]
+pub struct AnchorCommand {
+    #[structopt(short, long, global = true, help = "Which Horizon server to use")]
+    pub server: Option<String>,
+    #[structopt(subcommand)]
+    pub command: AnchorInnerCommand,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum AnchorInnerCommand {
+    #[structopt(about = "Shows the assets and the fields supported by an anchor")]
+    Info(sep6::InfoCommand),
+    #[structopt(about = "Asks an anchor how to deposit an asset")]
+    Deposit(sep6::DepositCommand),
+    #[structopt(about = "Withdraws an asset through an anchor")]
+    Withdraw(sep6::WithdrawCommand),
+    #[structopt(about = "Gets a firm SEP-38 quote to exchange assets")]
+    Quote(quote::QuoteCommand),
+    #[structopt(about = "Lists the deposits and withdrawals made through an anchor")]
+    Transactions(sep6::TransactionsCommand),
+}
+
+#[derive(Debug, StructOpt)]
+pub struct AnchorOptions {
+    #[structopt(
+        long,
+        help = "The anchor domain, use http://host:port for a local server"
+    )]
+    pub domain: String,
+    #[structopt(long = "as", help = "The account using the anchor, or its name")]
+    pub account: String,
+}
+
+/// An anchor discovered through its stellar.toml, with the account using it
+/// authenticated if the anchor supports SEP-10.
+pub struct Anchor {
+    pub domain: String,
+    pub toml: StellarToml,
+    pub keypair: KeyPair,
+    pub token: Option<String>,
+}
+
+pub async fn run_command(
+    mut out: &mut Output,
+    config: &AppConfig,
+    command: AnchorCommand,
+) -> Result<()> {
+    let client = wallet::horizon_client(&config, &command.server)?;
+    match command.command {
+        AnchorInnerCommand::Info(cmd) => sep6::run_info(&mut out, cmd).await,
+        AnchorInnerCommand::Deposit(cmd) => sep6::run_deposit(&mut out, &config, cmd).await,
+        AnchorInnerCommand::Withdraw(cmd) => {
+            sep6::run_withdraw(&mut out, &config, &client, cmd).await
+        }
+        AnchorInnerCommand::Quote(cmd) => quote::run_quote(&mut out, &config, cmd).await,
+        AnchorInnerCommand::Transactions(cmd) => {
+            sep6::run_transactions(&mut out, &config, cmd).await
+        }
+    }
+}
+
+impl Anchor {
+    pub async fn connect(config: &AppConfig, options: &AnchorOptions) -> Result<Anchor> {
+        let keypair = config.find_account(&options.account)?.keypair()?;
+        let toml = stellar_toml::fetch(&options.domain).await?;
+        let token = match toml.web_auth_endpoint {
+            Some(_) => Some(
+                auth::sep10_token(&options.domain, &keypair, false)
+                    .await?
+                    .token,
+            ),
+            None => None,
+        };
+        Ok(Anchor {
+            domain: stellar_toml::domain_host(&options.domain).to_string(),
+            toml,
+            keypair,
+            token,
+        })
+    }
+
+    pub fn account_id(&self) -> String {
+        self.keypair.public_key().account_id()
+    }
+
+    /// Returns the url of `path` on the anchor transfer server.
+    pub fn endpoint(&self, path: &str) -> Result<String> {
+        let server = self.toml.transfer_server(&self.domain)?;
+        Ok(format!("{}/{}", server, path))
+    }
+
+    /// Returns the url of `path` on the anchor SEP-38 quote server.
+    pub fn quote_endpoint(&self, path: &str) -> Result<String> {
+        let server = self.toml.anchor_quote_server(&self.domain)?;
+        Ok(format!("{}/{}", server, path))
+    }
+}
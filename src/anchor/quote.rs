@@ -0,0 +1,289 @@
+use super::{Anchor, AnchorOptions};
+use crate::config::AppConfig;
+use crate::error::Error;
+use crate::http::{self, RequestBody};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use convey::components::{newline, text};
+use convey::{Output, Render};
+use std::str::FromStr;
+use stellar_base::amount::Amount;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct QuoteCommand {
+    #[structopt(flatten)]
+    pub anchor: AnchorOptions,
+    #[structopt(
+        long,
+        help = "The asset to sell, as a code listed by the anchor or a SEP-38 asset like iso4217:EUR"
+    )]
+    pub sell: String,
+    #[structopt(long, help = "The asset to buy, in the same format as --sell")]
+    pub buy: String,
+    #[structopt(long, help = "The amount to sell")]
+    pub amount: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct PricesResponse {
+    buy_assets: Vec<BuyAsset>,
+}
+
+#[derive(Deserialize, Debug)]
+struct BuyAsset {
+    asset: String,
+    price: String,
+}
+
+/// A firm SEP-38 quote, valid until it expires.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Quote {
+    pub id: String,
+    pub expires_at: DateTime<Utc>,
+    pub price: String,
+    pub total_price: Option<String>,
+    pub sell_asset: String,
+    pub sell_amount: String,
+    pub buy_asset: String,
+    pub buy_amount: String,
+    pub fee: QuoteFee,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QuoteFee {
+    pub total: String,
+    pub asset: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AnchorQuote {
+    pub indicative_price: Option<String>,
+    pub quote: Quote,
+}
+
+pub async fn run_quote(out: &mut Output, config: &AppConfig, command: QuoteCommand) -> Result<()> {
+    Amount::from_str(&command.amount)?;
+    let anchor = Anchor::connect(config, &command.anchor).await?;
+    let sell_asset = sep38_asset(&anchor, &command.sell)?;
+    let buy_asset = sep38_asset(&anchor, &command.buy)?;
+
+    let url = http::with_query(
+        &anchor.quote_endpoint("prices")?,
+        &[
+            ("sell_asset", &sell_asset),
+            ("sell_amount", &command.amount),
+        ],
+    )?;
+    let prices: PricesResponse = http::get_json(&url, anchor.token.as_deref()).await?;
+    let indicative_price = prices
+        .buy_assets
+        .into_iter()
+        .find(|a| a.asset == buy_asset)
+        .map(|a| a.price);
+
+    let body = serde_json::json!({
+        "sell_asset": sell_asset,
+        "buy_asset": buy_asset,
+        "sell_amount": command.amount,
+        "context": "sep6",
+    });
+    let quote: Quote = http::post_json(
+        &anchor.quote_endpoint("quote")?,
+        anchor.token.as_deref(),
+        RequestBody::Json(body),
+    )
+    .await?;
+    out.print(AnchorQuote {
+        indicative_price,
+        quote,
+    })
+    .map_err(Error::Convey)?;
+    Ok(())
+}
+
+/// Fetches the firm quote with `id`, failing if it has expired.
+pub async fn fetch_quote(anchor: &Anchor, id: &str) -> Result<Quote> {
+    let url = format!("{}/{}", anchor.quote_endpoint("quote")?, id);
+    let quote: Quote = http::get_json(&url, anchor.token.as_deref()).await?;
+    if quote.expires_at < Utc::now() {
+        return Err(anyhow!("The quote {} expired at {}", id, quote.expires_at));
+    }
+    Ok(quote)
+}
+
+/// Returns `asset` in the SEP-38 format, looking up the issuer of plain
+/// asset codes in the anchor stellar.toml.
+pub fn sep38_asset(anchor: &Anchor, asset: &str) -> Result<String> {
+    if asset.contains(':') {
+        return Ok(asset.to_string());
+    }
+    let asset = anchor.toml.currency(&anchor.domain, asset)?;
+    let credit = asset
+        .as_credit()
+        .ok_or_else(|| anyhow!("The stellar.toml of {} lists a native asset", anchor.domain))?;
+    Ok(format!(
+        "stellar:{}:{}",
+        credit.code(),
+        credit.issuer().account_id()
+    ))
+}
+
+/// Checks that `quote` sells `amount` of `sell_asset`, given in the SEP-38
+/// format.
+pub fn check_quote_sells(quote: &Quote, sell_asset: &str, amount: &str) -> Result<()> {
+    if quote.sell_asset != sell_asset
+        || Amount::from_str(&quote.sell_amount)? != Amount::from_str(amount)?
+    {
+        return Err(anyhow!(
+            "The quote {} sells {} {}, not {} {}",
+            quote.id,
+            quote.sell_amount,
+            quote.sell_asset,
+            amount,
+            sell_asset
+        ));
+    }
+    Ok(())
+}
+
+impl Render for AnchorQuote {
+    fn render_for_humans(
+        &self,
+        mut fmt: &mut convey::human::Formatter,
+    ) -> std::result::Result<(), convey::Error> {
+        let quote = &self.quote;
+        let mut lines = vec![
+            ("Quote", quote.id.clone()),
+            (
+                "Sell",
+                format!("{} {}", quote.sell_amount, quote.sell_asset),
+            ),
+            ("Buy", format!("{} {}", quote.buy_amount, quote.buy_asset)),
+            ("Price", quote.price.clone()),
+        ];
+        if let Some(total_price) = &quote.total_price {
+            lines.push(("Total price", total_price.clone()));
+        }
+        if let Some(price) = &self.indicative_price {
+            lines.push(("Indicative price", price.clone()));
+        }
+        lines.push(("Fee", format!("{} {}", quote.fee.total, quote.fee.asset)));
+        lines.push(("Expires at", quote.expires_at.to_string()));
+        for (name, value) in lines {
+            text(name).render_for_humans(&mut fmt)?;
+            text("\t").render_for_humans(&mut fmt)?;
+            text(value).render_for_humans(&mut fmt)?;
+            newline().render_for_humans(&mut fmt)?;
+        }
+        Ok(())
+    }
+
+    render_json!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stellar_toml::{Currency, StellarToml};
+    use crate::test_server::TestServer;
+    use chrono::Duration;
+    use stellar_base::KeyPair;
+
+    const USDC_ISSUER: &str = "GA5ZSEJYB37JRC5AVCIA5MOP4RHTM335X2KGX3IHOJAPP5RE34K4KZVN";
+
+    fn anchor() -> Anchor {
+        Anchor {
+            domain: "testanchor.stellar.org".to_string(),
+            toml: StellarToml {
+                currencies: vec![
+                    Currency {
+                        code: Some("USDC".to_string()),
+                        issuer: Some(USDC_ISSUER.to_string()),
+                    },
+                    Currency {
+                        code: Some("SRT".to_string()),
+                        issuer: None,
+                    },
+                ],
+                ..StellarToml::default()
+            },
+            keypair: KeyPair::random().unwrap(),
+            token: None,
+        }
+    }
+
+    fn quote(sell_asset: &str, sell_amount: &str) -> Quote {
+        Quote {
+            id: "de762cda-a193-4961-861e-57b31fed6eb3".to_string(),
+            expires_at: Utc::now(),
+            price: "1.05".to_string(),
+            total_price: None,
+            sell_asset: sell_asset.to_string(),
+            sell_amount: sell_amount.to_string(),
+            buy_asset: "iso4217:EUR".to_string(),
+            buy_amount: "95.24".to_string(),
+            fee: QuoteFee {
+                total: "0".to_string(),
+                asset: sell_asset.to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn converts_codes_to_sep38_assets() {
+        let anchor = anchor();
+        assert_eq!(
+            sep38_asset(&anchor, "USDC").unwrap(),
+            format!("stellar:USDC:{}", USDC_ISSUER)
+        );
+        assert_eq!(sep38_asset(&anchor, "iso4217:EUR").unwrap(), "iso4217:EUR");
+        assert_eq!(
+            sep38_asset(&anchor, "EURC").unwrap_err().to_string(),
+            "The stellar.toml of testanchor.stellar.org does not list EURC"
+        );
+        assert_eq!(
+            sep38_asset(&anchor, "SRT").unwrap_err().to_string(),
+            "The stellar.toml of testanchor.stellar.org has no issuer for SRT"
+        );
+    }
+
+    #[test]
+    fn accepts_quote_selling_the_withdrawal() {
+        let usdc = format!("stellar:USDC:{}", USDC_ISSUER);
+        check_quote_sells(&quote(&usdc, "100"), &usdc, "100.0000000").unwrap();
+    }
+
+    #[test]
+    fn rejects_quote_selling_something_else() {
+        let usdc = format!("stellar:USDC:{}", USDC_ISSUER);
+        assert_eq!(
+            check_quote_sells(&quote(&usdc, "100"), &usdc, "50")
+                .unwrap_err()
+                .to_string(),
+            format!(
+                "The quote de762cda-a193-4961-861e-57b31fed6eb3 sells 100 {}, not 50 {}",
+                usdc, usdc
+            )
+        );
+        assert!(check_quote_sells(&quote("iso4217:USD", "100"), &usdc, "100").is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_expired_quote() {
+        let usdc = format!("stellar:USDC:{}", USDC_ISSUER);
+        let mut expired = quote(&usdc, "100");
+        expired.expires_at = Utc::now() - Duration::minutes(1);
+        let body = serde_json::to_string(&expired).unwrap();
+        let server = TestServer::start(move |_, _| (200, body.clone())).await;
+        let mut anchor = anchor();
+        anchor.toml.anchor_quote_server = Some(server.url.clone());
+
+        let error = fetch_quote(&anchor, &expired.id).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("The quote {} expired at {}", expired.id, expired.expires_at)
+        );
+        assert_eq!(server.requests()[0].path, format!("/quote/{}", expired.id));
+    }
+}
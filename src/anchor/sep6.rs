@@ -0,0 +1,436 @@
+use super::quote::{check_quote_sells, fetch_quote, sep38_asset};
+use super::{Anchor, AnchorOptions};
+use crate::config::AppConfig;
+use crate::error::Error;
+use crate::fee;
+use crate::http;
+use crate::render::ResponseRender;
+use crate::stellar_toml;
+use crate::tx::check::check_and_submit;
+use crate::tx::sign::explain_and_sign;
+use crate::wallet::{self, TransactionOptions, TransactionXdr};
+use anyhow::Result;
+use convey::components::{newline, text};
+use convey::{Output, Render};
+use serde_json::Value;
+use std::str::FromStr;
+use stellar_base::amount::Amount;
+use stellar_base::xdr::XDRSerialize;
+use stellar_base::{Operation, PublicKey};
+use stellar_horizon::client::HorizonClient;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct InfoCommand {
+    #[structopt(
+        long,
+        help = "The anchor domain, use http://host:port for a local server"
+    )]
+    pub domain: String,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct DepositCommand {
+    #[structopt(flatten)]
+    pub anchor: AnchorOptions,
+    #[structopt(long, help = "The code of the asset to deposit")]
+    pub asset: String,
+    #[structopt(long, help = "The amount to deposit")]
+    pub amount: Option<String>,
+    #[structopt(long = "type", help = "The deposit method, as listed by anchor info")]
+    pub kind: Option<String>,
+    #[structopt(
+        long = "field",
+        number_of_values = 1,
+        help = "An extra field for the anchor, as KEY=VALUE"
+    )]
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct WithdrawCommand {
+    #[structopt(flatten)]
+    pub anchor: AnchorOptions,
+    #[structopt(long, help = "The code of the asset to withdraw")]
+    pub asset: String,
+    #[structopt(long, help = "The amount to withdraw")]
+    pub amount: String,
+    #[structopt(
+        long = "type",
+        help = "The withdrawal method, as listed by anchor info"
+    )]
+    pub kind: String,
+    #[structopt(long, help = "The bank account or address receiving the funds")]
+    pub dest: Option<String>,
+    #[structopt(
+        long,
+        help = "Extra information about the destination, like a routing number"
+    )]
+    pub dest_extra: Option<String>,
+    #[structopt(
+        long = "field",
+        number_of_values = 1,
+        help = "An extra field for the anchor, as KEY=VALUE"
+    )]
+    pub fields: Vec<String>,
+    #[structopt(long, help = "Exchange the asset at the price of this firm quote")]
+    pub quote_id: Option<String>,
+    #[structopt(short, long, help = "Sign the payment without asking")]
+    pub yes: bool,
+    #[structopt(
+        long,
+        help = "Send even if the destination requires a memo and there is none"
+    )]
+    pub skip_memo_check: bool,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct TransactionsCommand {
+    #[structopt(flatten)]
+    pub anchor: AnchorOptions,
+    #[structopt(long, help = "The code of the asset")]
+    pub asset: String,
+    #[structopt(long, help = "Show only the anchor transaction with this id")]
+    pub id: Option<String>,
+    #[structopt(long, help = "The maximum number of transactions to show")]
+    pub limit: Option<u32>,
+}
+
+#[derive(Deserialize, Debug)]
+struct WithdrawInstructions {
+    account_id: Option<String>,
+    memo_type: Option<String>,
+    memo: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AnchorTransaction {
+    pub id: String,
+    pub kind: Option<String>,
+    pub status: String,
+    pub amount_in: Option<String>,
+    pub amount_out: Option<String>,
+    pub amount_fee: Option<String>,
+    pub started_at: Option<String>,
+    pub completed_at: Option<String>,
+    pub stellar_transaction_id: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AnchorTransactionList {
+    pub transactions: Vec<AnchorTransaction>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TransactionResponse {
+    transaction: AnchorTransaction,
+}
+
+pub async fn run_info(out: &mut Output, command: InfoCommand) -> Result<()> {
+    let toml = stellar_toml::fetch(&command.domain).await?;
+    let server = toml.transfer_server(stellar_toml::domain_host(&command.domain))?;
+    let info: Value = http::get_json(&format!("{}/info", server), None).await?;
+    out.print(ResponseRender(info)).map_err(Error::Convey)?;
+    Ok(())
+}
+
+pub async fn run_deposit(
+    out: &mut Output,
+    config: &AppConfig,
+    command: DepositCommand,
+) -> Result<()> {
+    let anchor = Anchor::connect(config, &command.anchor).await?;
+    let account = anchor.account_id();
+    let mut params = vec![
+        ("asset_code", command.asset.as_str()),
+        ("account", account.as_str()),
+    ];
+    if let Some(amount) = &command.amount {
+        params.push(("amount", amount));
+    }
+    if let Some(kind) = &command.kind {
+        params.push(("type", kind));
+    }
+    let fields = parse_fields(&command.fields)?;
+    params.extend(fields);
+    let url = http::with_query(&anchor.endpoint("deposit")?, &params)?;
+    let response: Value = http::get_json(&url, anchor.token.as_deref()).await?;
+    out.print(ResponseRender(response)).map_err(Error::Convey)?;
+    Ok(())
+}
+
+pub async fn run_withdraw<H>(
+    mut out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: WithdrawCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let anchor = Anchor::connect(config, &command.anchor).await?;
+    let asset = anchor.toml.currency(&anchor.domain, &command.asset)?;
+    let amount = Amount::from_str(&command.amount)?;
+    let response = request_withdrawal(&anchor, &command).await?;
+    out.print(ResponseRender(response.clone()))
+        .map_err(Error::Convey)?;
+
+    let instructions: WithdrawInstructions = serde_json::from_value(response)
+        .map_err(|err| anyhow!("Invalid withdrawal instructions: {}", err))?;
+    let destination = instructions
+        .account_id
+        .ok_or_else(|| anyhow!("The anchor did not send the account to pay"))?;
+    let payment = Operation::new_payment()
+        .with_destination(PublicKey::from_account_id(&destination)?)
+        .with_amount(amount)?
+        .with_asset(asset)
+        .build()?;
+    let mut tx =
+        wallet::build_transaction(client, anchor.keypair.public_key(), vec![payment]).await?;
+    if let Some(memo) = &instructions.memo {
+        let memo_type = instructions.memo_type.as_deref().unwrap_or("text");
+        *tx.memo_mut() = wallet::parse_memo(memo, memo_type)?;
+    }
+    if !command.skip_memo_check {
+        wallet::check_memo_required(client, &tx).await?;
+    }
+    fee::apply_fee_strategy(out, client, &command.transaction, &mut tx).await?;
+
+    let envelope = explain_and_sign(
+        &mut out,
+        config,
+        client,
+        tx.into_envelope(),
+        std::slice::from_ref(&command.anchor.account),
+        command.yes,
+    )
+    .await?;
+    if command.transaction.no_submit {
+        let xdr = envelope.xdr_base64()?;
+        out.print(TransactionXdr { xdr }).map_err(Error::Convey)?;
+        return Ok(());
+    }
+    let network = wallet::network(client).await?;
+    check_and_submit(&mut out, client, &envelope, &network).await
+}
+
+/// Asks the anchor how to withdraw, through the SEP-38 quote if there is one.
+async fn request_withdrawal(anchor: &Anchor, command: &WithdrawCommand) -> Result<Value> {
+    let account = anchor.account_id();
+    let mut params = vec![
+        ("type", command.kind.as_str()),
+        ("amount", command.amount.as_str()),
+        ("account", account.as_str()),
+    ];
+    let quote = match &command.quote_id {
+        Some(id) => Some(fetch_quote(anchor, id).await?),
+        None => None,
+    };
+    let endpoint = match &quote {
+        Some(quote) => {
+            let sell_asset = sep38_asset(anchor, &command.asset)?;
+            check_quote_sells(quote, &sell_asset, &command.amount)?;
+            params.push(("source_asset", command.asset.as_str()));
+            params.push(("destination_asset", quote.buy_asset.as_str()));
+            params.push(("quote_id", quote.id.as_str()));
+            "withdraw-exchange"
+        }
+        None => {
+            params.push(("asset_code", command.asset.as_str()));
+            "withdraw"
+        }
+    };
+    if let Some(dest) = &command.dest {
+        params.push(("dest", dest));
+    }
+    if let Some(dest_extra) = &command.dest_extra {
+        params.push(("dest_extra", dest_extra));
+    }
+    let fields = parse_fields(&command.fields)?;
+    params.extend(fields);
+    let url = http::with_query(&anchor.endpoint(endpoint)?, &params)?;
+    http::get_json(&url, anchor.token.as_deref()).await
+}
+
+pub async fn run_transactions(
+    out: &mut Output,
+    config: &AppConfig,
+    command: TransactionsCommand,
+) -> Result<()> {
+    let anchor = Anchor::connect(config, &command.anchor).await?;
+    let transactions = match &command.id {
+        Some(id) => {
+            let url = http::with_query(&anchor.endpoint("transaction")?, &[("id", id)])?;
+            let response: TransactionResponse =
+                http::get_json(&url, anchor.token.as_deref()).await?;
+            vec![response.transaction]
+        }
+        None => {
+            let account = anchor.account_id();
+            let limit = command.limit.map(|l| l.to_string());
+            let mut params = vec![
+                ("asset_code", command.asset.as_str()),
+                ("account", account.as_str()),
+            ];
+            if let Some(limit) = &limit {
+                params.push(("limit", limit));
+            }
+            let url = http::with_query(&anchor.endpoint("transactions")?, &params)?;
+            let response: AnchorTransactionList =
+                http::get_json(&url, anchor.token.as_deref()).await?;
+            response.transactions
+        }
+    };
+    out.print(AnchorTransactionList { transactions })
+        .map_err(Error::Convey)?;
+    Ok(())
+}
+
+/// Splits the KEY=VALUE fields given on the command line.
+fn parse_fields(fields: &[String]) -> Result<Vec<(&str, &str)>> {
+    fields
+        .iter()
+        .map(|field| {
+            let mut parts = field.splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) if !key.is_empty() => Ok((key, value)),
+                _ => Err(anyhow!("Invalid field {}, use KEY=VALUE", field)),
+            }
+        })
+        .collect()
+}
+
+impl Render for AnchorTransaction {
+    fn render_for_humans(
+        &self,
+        mut fmt: &mut convey::human::Formatter,
+    ) -> std::result::Result<(), convey::Error> {
+        let fields = [
+            Some(self.id.as_str()),
+            self.kind.as_deref(),
+            Some(self.status.as_str()),
+            self.amount_in.as_deref(),
+            self.amount_out.as_deref(),
+            self.started_at.as_deref(),
+        ];
+        for (index, field) in fields.iter().enumerate() {
+            if index > 0 {
+                text("\t").render_for_humans(&mut fmt)?;
+            }
+            text(field.unwrap_or("-")).render_for_humans(&mut fmt)?;
+        }
+        newline().render_for_humans(&mut fmt)?;
+        Ok(())
+    }
+
+    render_json!();
+}
+
+impl Render for AnchorTransactionList {
+    fn render_for_humans(
+        &self,
+        mut fmt: &mut convey::human::Formatter,
+    ) -> std::result::Result<(), convey::Error> {
+        for transaction in &self.transactions {
+            transaction.render_for_humans(&mut fmt)?;
+        }
+        Ok(())
+    }
+
+    render_json!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stellar_toml::{Currency, StellarToml};
+    use crate::test_server::TestServer;
+    use chrono::{Duration, Utc};
+    use stellar_base::KeyPair;
+
+    const USDC_ISSUER: &str = "GA5ZSEJYB37JRC5AVCIA5MOP4RHTM335X2KGX3IHOJAPP5RE34K4KZVN";
+    const QUOTE_ID: &str = "de762cda-a193-4961-861e-57b31fed6eb3";
+
+    #[tokio::test]
+    async fn withdraws_through_quote() {
+        let usdc = format!("stellar:USDC:{}", USDC_ISSUER);
+        let quote = serde_json::json!({
+            "id": QUOTE_ID,
+            "expires_at": Utc::now() + Duration::minutes(5),
+            "price": "1.05",
+            "sell_asset": usdc,
+            "sell_amount": "100",
+            "buy_asset": "iso4217:EUR",
+            "buy_amount": "95.24",
+            "fee": { "total": "0", "asset": usdc },
+        });
+        let server = TestServer::start(move |_, request| match request.path.as_str() {
+            "/quote/de762cda-a193-4961-861e-57b31fed6eb3" => (200, quote.to_string()),
+            "/withdraw-exchange" => (
+                200,
+                r#"{"account_id": "GB43KVROR7TFJ6KAPCYRF2FJROTZAH4FHLTJLPWX4DRZCC5NASLGITR6"}"#
+                    .to_string(),
+            ),
+            _ => (404, "{}".to_string()),
+        })
+        .await;
+        let anchor = Anchor {
+            domain: stellar_toml::domain_host(&server.url).to_string(),
+            toml: StellarToml {
+                transfer_server: Some(server.url.clone()),
+                anchor_quote_server: Some(server.url.clone()),
+                currencies: vec![Currency {
+                    code: Some("USDC".to_string()),
+                    issuer: Some(USDC_ISSUER.to_string()),
+                }],
+                ..StellarToml::default()
+            },
+            keypair: KeyPair::random().unwrap(),
+            token: None,
+        };
+        let command = WithdrawCommand::from_iter(&[
+            "withdraw",
+            "--domain",
+            &server.url,
+            "--as",
+            "alice",
+            "--asset",
+            "USDC",
+            "--amount",
+            "100",
+            "--type",
+            "SEPA",
+            "--dest",
+            "DE89370400440532013000",
+            "--quote-id",
+            QUOTE_ID,
+        ]);
+
+        request_withdrawal(&anchor, &command).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].path, format!("/quote/{}", QUOTE_ID));
+        let withdraw = &requests[1];
+        assert_eq!(withdraw.path, "/withdraw-exchange");
+        let account = anchor.account_id();
+        let expected = vec![
+            ("type", "SEPA"),
+            ("amount", "100"),
+            ("account", account.as_str()),
+            ("source_asset", "USDC"),
+            ("destination_asset", "iso4217:EUR"),
+            ("quote_id", QUOTE_ID),
+            ("dest", "DE89370400440532013000"),
+        ];
+        let query: Vec<(&str, &str)> = withdraw
+            .query
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        assert_eq!(query, expected);
+    }
+}
@@ -0,0 +1,155 @@
+use super::{find_balance, parse_asset};
+use crate::config::AppConfig;
+use crate::wallet::{self, TransactionOptions};
+use anyhow::Result;
+use convey::Output;
+use stellar_base::account::TrustLineFlags;
+use stellar_base::Operation;
+use stellar_horizon::client::HorizonClient;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct AuthorizeCommand {
+    #[structopt(
+        name = "HOLDER",
+        help = "The account holding the asset, starts with G, or its name"
+    )]
+    pub holder: String,
+    #[structopt(
+        name = "ASSET",
+        help = "The asset, as CODE:ISSUER. The issuer must be in the config."
+    )]
+    pub asset: String,
+    #[structopt(
+        long,
+        help = "Only allow the holder to keep its offers, not to send or receive the asset"
+    )]
+    pub maintain_liabilities: bool,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct DeauthorizeCommand {
+    #[structopt(
+        name = "HOLDER",
+        help = "The account holding the asset, starts with G, or its name"
+    )]
+    pub holder: String,
+    #[structopt(
+        name = "ASSET",
+        help = "The asset, as CODE:ISSUER. The issuer must be in the config."
+    )]
+    pub asset: String,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+pub async fn run_authorize<H>(
+    out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: AuthorizeCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let flags = if command.maintain_liabilities {
+        TrustLineFlags::AUTHORIZED_TO_MAINTAIN_LIABILITIES
+    } else {
+        TrustLineFlags::AUTHORIZED
+    };
+    set_authorization(
+        out,
+        config,
+        client,
+        &command.holder,
+        &command.asset,
+        flags,
+        &command.transaction,
+    )
+    .await
+}
+
+pub async fn run_deauthorize<H>(
+    out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: DeauthorizeCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    set_authorization(
+        out,
+        config,
+        client,
+        &command.holder,
+        &command.asset,
+        TrustLineFlags::empty(),
+        &command.transaction,
+    )
+    .await
+}
+
+/// Changes the authorization of the `holder` trustline to `asset`, checking
+/// that the issuer flags allow it.
+async fn set_authorization<H>(
+    mut out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    holder: &str,
+    asset: &str,
+    flags: TrustLineFlags,
+    options: &TransactionOptions,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let asset = parse_asset(asset)?;
+    let credit = asset
+        .as_credit()
+        .ok_or_else(|| anyhow!("XLM has no issuer to authorize holders"))?;
+    let issuer = config
+        .find_account(&credit.issuer().account_id())?
+        .keypair()?;
+    let holder = config.resolve_public_key(holder)?;
+    let issuer_account = wallet::load_account(client, issuer.public_key()).await?;
+    let holder_account = wallet::load_account(client, &holder).await?;
+    let trustline = find_balance(&holder_account, &asset)
+        .ok_or_else(|| anyhow!("Account {} has no trustline to {}", holder, credit.code()))?;
+
+    let authorized = trustline.is_authorized.unwrap_or(false);
+    let maintains_liabilities = trustline
+        .is_authorized_to_maintain_liabilities
+        .unwrap_or(false);
+    let current = if authorized {
+        TrustLineFlags::AUTHORIZED
+    } else if maintains_liabilities {
+        TrustLineFlags::AUTHORIZED_TO_MAINTAIN_LIABILITIES
+    } else {
+        TrustLineFlags::empty()
+    };
+    if current == flags {
+        return Err(anyhow!(
+            "The trustline of {} already has this authorization",
+            holder
+        ));
+    }
+    // Lowering the authorization of a trustline is a revocation.
+    let revokes = authorized || (maintains_liabilities && flags.is_empty());
+    if revokes && !issuer_account.flags.auth_revocable {
+        return Err(anyhow!(
+            "The issuer of {} does not have the auth_revocable flag, it cannot revoke authorizations",
+            credit.code()
+        ));
+    }
+
+    let operation = Operation::new_allow_trust()
+        .with_trustor(holder)
+        .with_asset(credit.asset_type())
+        .with_authorize_flags(flags)
+        .build()?;
+    let tx = wallet::build_transaction(client, issuer.public_key(), vec![operation]).await?;
+    wallet::sign_and_submit(&mut out, client, options, tx, &[issuer]).await
+}
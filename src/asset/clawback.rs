@@ -0,0 +1,131 @@
+use super::{find_balance, load_issuer_flags, parse_asset};
+use crate::claimable_balance::parse_balance_id;
+use crate::config::AppConfig;
+use crate::raw_tx;
+use crate::wallet::{self, TransactionOptions};
+use anyhow::Result;
+use convey::Output;
+use std::convert::TryInto;
+use std::str::FromStr;
+use stellar_base::amount::Amount;
+use stellar_base::PublicKey;
+use stellar_horizon::api;
+use stellar_horizon::client::HorizonClient;
+use stellar_xdr::curr as xdr;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct ClawbackCommand {
+    #[structopt(
+        name = "HOLDER",
+        help = "The account holding the asset, starts with G, or its name"
+    )]
+    pub holder: String,
+    #[structopt(
+        name = "ASSET",
+        help = "The asset, as CODE:ISSUER. The issuer must be in the config."
+    )]
+    pub asset: String,
+    #[structopt(name = "AMOUNT", help = "The amount to claw back")]
+    pub amount: String,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ClawbackBalanceCommand {
+    #[structopt(
+        name = "BALANCE_ID",
+        help = "The claimable balance id. The issuer of its asset must be in the config."
+    )]
+    pub balance_id: String,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+pub async fn run_clawback<H>(
+    mut out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: ClawbackCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let asset = parse_asset(&command.asset)?;
+    let credit = asset
+        .as_credit()
+        .ok_or_else(|| anyhow!("XLM has no issuer to claw it back"))?;
+    let issuer = config
+        .find_account(&credit.issuer().account_id())?
+        .keypair()?;
+    let holder = config.resolve_public_key(&command.holder)?;
+    let amount = Amount::from_str(&command.amount)?.to_stroops()?;
+    check_clawback_enabled(client, credit.issuer(), credit.code()).await?;
+    let holder_account = wallet::load_account(client, &holder).await?;
+    let trustline = find_balance(&holder_account, &asset)
+        .ok_or_else(|| anyhow!("Account {} has no trustline to {}", holder, credit.code()))?;
+    if Amount::from_str(&trustline.balance)?.to_stroops()? < amount {
+        return Err(anyhow!(
+            "Account {} holds only {} {}",
+            holder,
+            trustline.balance,
+            credit.code()
+        ));
+    }
+
+    let operation = xdr::OperationBody::Clawback(xdr::ClawbackOp {
+        asset: raw_tx::asset(&asset)?,
+        from: raw_tx::muxed_account(&holder)?,
+        amount: amount.to_i64(),
+    });
+    let tx = raw_tx::build_transaction(client, issuer.public_key(), vec![operation]).await?;
+    raw_tx::sign_and_submit(&mut out, client, &command.transaction, tx, &[issuer]).await
+}
+
+pub async fn run_clawback_balance<H>(
+    mut out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: ClawbackBalanceCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let balance_id = parse_balance_id(&command.balance_id)?;
+    let hash: [u8; 32] = balance_id
+        .as_bytes()
+        .try_into()
+        .map_err(|_| anyhow!("Invalid claimable balance id"))?;
+    // Horizon ids start with the type of the id, 0 as four bytes.
+    let id = format!("00000000{}", hex::encode(hash));
+    let (_, balance) = client.request(api::claimable_balances::single(id)).await?;
+    let asset = parse_asset(&balance.asset)?;
+    let credit = asset
+        .as_credit()
+        .ok_or_else(|| anyhow!("The claimable balance holds XLM, it cannot be clawed back"))?;
+    let issuer = config
+        .find_account(&credit.issuer().account_id())?
+        .keypair()?;
+    check_clawback_enabled(client, credit.issuer(), credit.code()).await?;
+
+    let operation = xdr::OperationBody::ClawbackClaimableBalance(xdr::ClawbackClaimableBalanceOp {
+        balance_id: xdr::ClaimableBalanceId::ClaimableBalanceIdTypeV0(xdr::Hash(hash)),
+    });
+    let tx = raw_tx::build_transaction(client, issuer.public_key(), vec![operation]).await?;
+    raw_tx::sign_and_submit(&mut out, client, &command.transaction, tx, &[issuer]).await
+}
+
+async fn check_clawback_enabled<H>(client: &H, issuer: &PublicKey, code: &str) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let flags = load_issuer_flags(client, issuer).await?;
+    if !flags.auth_clawback_enabled {
+        return Err(anyhow!(
+            "The issuer of {} does not have the auth_clawback_enabled flag, it cannot claw back",
+            code
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,204 @@
+use super::{find_balance, load_issuer_flags};
+use crate::config::AppConfig;
+use crate::error::Error;
+use crate::raw_tx::{self, RawTransaction};
+use crate::wallet::{self, TransactionOptions};
+use anyhow::Result;
+use convey::components::{newline, text};
+use convey::{Output, Render};
+use std::str::FromStr;
+use stellar_base::account::{AccountFlags, TrustLineFlags};
+use stellar_base::amount::{Amount, Stroops};
+use stellar_base::asset::CreditAsset;
+use stellar_base::{Asset, KeyPair, Operation};
+use stellar_horizon::client::HorizonClient;
+use stellar_xdr::curr as xdr;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct IssueCommand {
+    #[structopt(name = "CODE", help = "The asset code, up to 12 characters")]
+    pub code: String,
+    #[structopt(long, help = "The issuing account, or its name")]
+    pub issuer: String,
+    #[structopt(
+        long,
+        help = "The distribution account receiving the supply, or its name"
+    )]
+    pub distributor: String,
+    #[structopt(long, help = "The amount issued to the distributor")]
+    pub amount: String,
+    #[structopt(long, help = "Holders must be authorized by the issuer")]
+    pub auth_required: bool,
+    #[structopt(long, help = "The issuer can revoke the authorization of holders")]
+    pub auth_revocable: bool,
+    #[structopt(
+        long,
+        help = "The issuer can claw back the asset from holders, needs the issuer to be revocable"
+    )]
+    pub auth_clawback: bool,
+    #[structopt(
+        long,
+        help = "Set the issuer master weight to zero, so that no more of the asset can be issued"
+    )]
+    pub lock: bool,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IssueStep {
+    pub step: String,
+    pub hash: String,
+}
+
+/// A transaction of the issuance, with the account signing it.
+struct Step<'a> {
+    description: String,
+    tx: RawTransaction,
+    signer: &'a KeyPair,
+}
+
+pub async fn run_issue<H>(
+    out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: IssueCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let issuer = config.find_account(&command.issuer)?.keypair()?;
+    let distributor = config.find_account(&command.distributor)?.keypair()?;
+    if issuer.public_key() == distributor.public_key() {
+        return Err(anyhow!(
+            "The issuer and the distributor must be different accounts"
+        ));
+    }
+    let credit = CreditAsset::new(command.code.clone(), issuer.public_key().clone())?;
+    let asset = Asset::Credit(credit.clone());
+    let amount = Amount::from_str(&command.amount)?;
+    let issuer_account = wallet::load_account(client, issuer.public_key()).await?;
+    let issuer_flags = load_issuer_flags(client, issuer.public_key()).await?;
+    let distributor_account = wallet::load_account(client, distributor.public_key()).await?;
+    let mut issuer_sequence = wallet::next_sequence(&issuer_account)?;
+    let distributor_sequence = wallet::next_sequence(&distributor_account)?;
+
+    let mut steps = Vec::new();
+    if command.auth_clawback && !command.auth_revocable && !issuer_flags.auth_revocable {
+        return Err(anyhow!(
+            "Clawback needs a revocable issuer, add --auth-revocable"
+        ));
+    }
+    let mut flags = 0;
+    if command.auth_required && !issuer_flags.auth_required {
+        flags |= AccountFlags::AUTH_REQUIRED.bits();
+    }
+    if command.auth_revocable && !issuer_flags.auth_revocable {
+        flags |= AccountFlags::AUTH_REVOCABLE.bits();
+    }
+    if command.auth_clawback && !issuer_flags.auth_clawback_enabled {
+        flags |= xdr::AccountFlags::ClawbackEnabledFlag as u32;
+    }
+    if flags != 0 {
+        steps.push(Step {
+            description: "Set the issuer flags".to_string(),
+            tx: RawTransaction::new(
+                issuer.public_key(),
+                issuer_sequence,
+                vec![raw_tx::set_flags(flags)],
+            )?,
+            signer: &issuer,
+        });
+        issuer_sequence += 1;
+    }
+
+    // The trustline is left as is when issuing more of an existing asset.
+    let trustline = find_balance(&distributor_account, &asset);
+    if trustline.is_none() {
+        let operation = Operation::new_change_trust()
+            .with_asset(asset.clone())
+            .with_limit(Some(Stroops::max()))?
+            .build()?;
+        steps.push(Step {
+            description: "Create the distributor trustline".to_string(),
+            tx: RawTransaction::new(
+                distributor.public_key(),
+                distributor_sequence,
+                vec![raw_tx::operation(&operation)?],
+            )?,
+            signer: &distributor,
+        });
+    }
+    let auth_required = command.auth_required || issuer_flags.auth_required;
+    let authorized = trustline.and_then(|t| t.is_authorized).unwrap_or(false);
+    if auth_required && !authorized {
+        let operation = Operation::new_allow_trust()
+            .with_trustor(distributor.public_key().clone())
+            .with_asset(credit.asset_type())
+            .with_authorize_flags(TrustLineFlags::AUTHORIZED)
+            .build()?;
+        steps.push(Step {
+            description: "Authorize the distributor trustline".to_string(),
+            tx: RawTransaction::new(
+                issuer.public_key(),
+                issuer_sequence,
+                vec![raw_tx::operation(&operation)?],
+            )?,
+            signer: &issuer,
+        });
+        issuer_sequence += 1;
+    }
+
+    let operation = Operation::new_payment()
+        .with_destination(distributor.public_key().clone())
+        .with_amount(amount)?
+        .with_asset(asset)
+        .build()?;
+    steps.push(Step {
+        description: format!("Issue {} {}", command.amount, command.code),
+        tx: RawTransaction::new(
+            issuer.public_key(),
+            issuer_sequence,
+            vec![raw_tx::operation(&operation)?],
+        )?,
+        signer: &issuer,
+    });
+    issuer_sequence += 1;
+
+    if command.lock {
+        let operation = Operation::new_set_options()
+            .with_master_weight(Some(0))
+            .build()?;
+        steps.push(Step {
+            description: "Lock the issuer".to_string(),
+            tx: RawTransaction::new(
+                issuer.public_key(),
+                issuer_sequence,
+                vec![raw_tx::operation(&operation)?],
+            )?,
+            signer: &issuer,
+        });
+    }
+
+    let network = wallet::network(client).await?;
+    for mut step in steps {
+        raw_tx::apply_fee_strategy(out, client, &command.transaction, &mut step.tx).await?;
+        step.tx.sign(step.signer, &network)?;
+        out.print(IssueStep {
+            step: step.description,
+            hash: hex::encode(step.tx.hash(&network)?),
+        })
+        .map_err(Error::Convey)?;
+        raw_tx::submit(out, client, &command.transaction, &step.tx).await?;
+    }
+    Ok(())
+}
+
+impl Render for IssueStep {
+    render_for_humans!(self -> [
+        text(&self.step), text("\t"), text(&self.hash), newline(),
+    ]);
+
+    render_json!();
+}
@@ -0,0 +1,144 @@
+use crate::config::AppConfig;
+use crate::wallet;
+use anyhow::Result;
+use convey::Output;
+use stellar_base::{Asset, PublicKey};
+use stellar_horizon::client::HorizonClient;
+use stellar_horizon::request::Request;
+use stellar_horizon::resources;
+use structopt::StructOpt;
+use url::Url;
+
+mod authorize;
+mod clawback;
+mod issue;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Issue and control assets")]
+pub struct AssetCommand {
+    #[structopt(short, long, global = true, help = "Which Horizon server to use")]
+    pub server: Option<String>,
+    #[structopt(subcommand)]
+    pub command: AssetInnerCommand,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum AssetInnerCommand {
+    #[structopt(about = "Issues a new asset to a distribution account")]
+    Issue(issue::IssueCommand),
+    #[structopt(about = "Authorizes an account to hold an asset")]
+    Authorize(authorize::AuthorizeCommand),
+    #[structopt(about = "Revokes the authorization of an account to hold an asset")]
+    Deauthorize(authorize::DeauthorizeCommand),
+    #[structopt(about = "Claws back an asset from an account")]
+    Clawback(clawback::ClawbackCommand),
+    #[structopt(about = "Claws back a claimable balance")]
+    ClawbackBalance(clawback::ClawbackBalanceCommand),
+}
+
+pub async fn run_command(
+    mut out: &mut Output,
+    config: &AppConfig,
+    command: AssetCommand,
+) -> Result<()> {
+    let client = wallet::horizon_client(&config, &command.server)?;
+    match command.command {
+        AssetInnerCommand::Issue(cmd) => issue::run_issue(&mut out, &config, &client, cmd).await,
+        AssetInnerCommand::Authorize(cmd) => {
+            authorize::run_authorize(&mut out, &config, &client, cmd).await
+        }
+        AssetInnerCommand::Deauthorize(cmd) => {
+            authorize::run_deauthorize(&mut out, &config, &client, cmd).await
+        }
+        AssetInnerCommand::Clawback(cmd) => {
+            clawback::run_clawback(&mut out, &config, &client, cmd).await
+        }
+        AssetInnerCommand::ClawbackBalance(cmd) => {
+            clawback::run_clawback_balance(&mut out, &config, &client, cmd).await
+        }
+    }
+}
+
+pub fn parse_asset(asset: &str) -> Result<Asset> {
+    let parts: Vec<&str> = asset.split(':').collect();
+    if parts.len() == 1 {
+        let code = parts[0].to_ascii_uppercase();
+        if code == "XLM" || code == "NATIVE" {
+            return Ok(Asset::new_native());
+        }
+    }
+    if parts.len() == 2 {
+        let issuer = PublicKey::from_account_id(parts[1])?;
+        let result = Asset::new_credit(parts[0], issuer)?;
+        return Ok(result);
+    }
+    Err(anyhow!("Invalid asset format"))
+}
+
+pub fn format_asset(asset: &Asset) -> String {
+    match asset.as_credit() {
+        None => "XLM".to_string(),
+        Some(credit) => format!("{}:{}", credit.code(), credit.issuer().account_id()),
+    }
+}
+
+pub fn format_resource_asset(asset: &resources::Asset) -> String {
+    match (&asset.asset_code, &asset.asset_issuer) {
+        (Some(code), Some(issuer)) => format!("{}:{}", code, issuer),
+        _ => "XLM".to_string(),
+    }
+}
+
+pub fn parse_resource_asset(asset: &resources::Asset) -> Result<Asset> {
+    parse_asset(&format_resource_asset(asset))
+}
+
+pub fn find_balance<'a>(
+    account: &'a resources::Account,
+    asset: &Asset,
+) -> Option<&'a resources::Balance> {
+    let asset = format_asset(asset);
+    account
+        .balances
+        .iter()
+        .find(|b| format_resource_asset(&b.asset) == asset)
+}
+
+/// The flags of an issuer, with the clawback flag missing from the account
+/// resource of stellar-horizon.
+#[derive(Deserialize, Debug)]
+pub struct IssuerFlags {
+    pub auth_required: bool,
+    pub auth_revocable: bool,
+    #[serde(default)]
+    pub auth_clawback_enabled: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct AccountFlagsResponse {
+    flags: IssuerFlags,
+}
+
+/// Horizon request for an account, reading only its flags.
+struct AccountFlagsRequest {
+    account_id: String,
+}
+
+impl Request for AccountFlagsRequest {
+    type Response = AccountFlagsResponse;
+
+    fn uri(&self, host: &Url) -> stellar_horizon::error::Result<Url> {
+        Ok(host.join(&format!("/accounts/{}", self.account_id))?)
+    }
+}
+
+pub async fn load_issuer_flags<H>(client: &H, issuer: &PublicKey) -> Result<IssuerFlags>
+where
+    H: HorizonClient,
+{
+    let request = AccountFlagsRequest {
+        account_id: issuer.account_id(),
+    };
+    let (_, response) = client.request(request).await?;
+    Ok(response.flags)
+}
@@ -0,0 +1,523 @@
+use crate::config::AppConfig;
+use crate::error::Error;
+use crate::http::{self, RequestBody};
+use crate::stellar_toml;
+use crate::tx::muxed_public_key;
+use anyhow::Result;
+use chrono::{DateTime, TimeZone, Utc};
+use convey::components::{newline, text};
+use convey::{Output, Render};
+use stellar_base::signature::SignatureHint;
+use stellar_base::transaction::{Transaction, TransactionEnvelope};
+use stellar_base::xdr::{XDRDeserialize, XDRSerialize};
+use stellar_base::{KeyPair, Network, Operation, PublicKey};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Authenticate with anchors")]
+pub struct AuthCommand {
+    #[structopt(subcommand)]
+    pub command: AuthInnerCommand,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum AuthInnerCommand {
+    #[structopt(about = "Gets a SEP-10 web authentication token")]
+    Sep10(Sep10Command),
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Sep10Command {
+    #[structopt(
+        long,
+        help = "The anchor domain, use http://host:port for a local server"
+    )]
+    pub domain: String,
+    #[structopt(long = "as", help = "The account to authenticate as, or its name")]
+    pub account: String,
+    #[structopt(long, help = "Get a new token even if a cached one is still valid")]
+    pub refresh: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuthToken {
+    pub domain: String,
+    pub account: String,
+    pub token: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// The SEP-10 tokens received, stored until they expire.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct TokenCache {
+    pub tokens: Vec<AuthToken>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ChallengeResponse {
+    transaction: String,
+    network_passphrase: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TokenResponse {
+    token: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct TokenClaims {
+    exp: Option<i64>,
+}
+
+pub async fn run_command(out: &mut Output, config: &AppConfig, command: AuthCommand) -> Result<()> {
+    match command.command {
+        AuthInnerCommand::Sep10(cmd) => {
+            let keypair = config.find_account(&cmd.account)?.keypair()?;
+            let token = sep10_token(&cmd.domain, &keypair, cmd.refresh).await?;
+            out.print(token).map_err(Error::Convey)?;
+            Ok(())
+        }
+    }
+}
+
+/// Returns a SEP-10 token for `keypair` on `domain`, from the cache if there
+/// is a valid one.
+pub async fn sep10_token(domain: &str, keypair: &KeyPair, refresh: bool) -> Result<AuthToken> {
+    let home_domain = stellar_toml::domain_host(domain).to_string();
+    let account = keypair.public_key().account_id();
+    let mut cache: TokenCache = confy::load("aurora-tokens")?;
+    let now = Utc::now();
+    cache
+        .tokens
+        .retain(|t| t.expires_at.map(|e| e > now).unwrap_or(false));
+    if !refresh {
+        if let Some(token) = cache
+            .tokens
+            .iter()
+            .find(|t| t.domain == home_domain && t.account == account)
+        {
+            return Ok(token.clone());
+        }
+    }
+
+    let token = request_token(domain, keypair).await?;
+    cache
+        .tokens
+        .retain(|t| t.domain != token.domain || t.account != token.account);
+    if token.expires_at.is_some() {
+        cache.tokens.push(token.clone());
+    }
+    confy::store("aurora-tokens", &cache)?;
+    Ok(token)
+}
+
+/// Requests a new SEP-10 token for `keypair` from the web auth endpoint of
+/// `domain`, signing the challenge it returns.
+async fn request_token(domain: &str, keypair: &KeyPair) -> Result<AuthToken> {
+    let home_domain = stellar_toml::domain_host(domain).to_string();
+    let account = keypair.public_key().account_id();
+    let toml = stellar_toml::fetch(domain).await?;
+    let endpoint = toml.web_auth_endpoint(&home_domain)?;
+    let server_key = toml.signing_key(&home_domain)?;
+    let url = http::with_query(endpoint, &[("account", &account)])?;
+    let challenge: ChallengeResponse = http::get_json(&url, None).await?;
+    let passphrase = match (&toml.network_passphrase, &challenge.network_passphrase) {
+        (Some(expected), Some(passphrase)) if expected != passphrase => {
+            return Err(anyhow!(
+                "The challenge is for the network '{}' but {} is on '{}'",
+                passphrase,
+                home_domain,
+                expected
+            ))
+        }
+        (Some(passphrase), _) | (None, Some(passphrase)) => passphrase.clone(),
+        (None, None) => {
+            return Err(anyhow!(
+                "The stellar.toml of {} has no NETWORK_PASSPHRASE",
+                home_domain
+            ))
+        }
+    };
+    let network = Network::new(passphrase);
+
+    let mut tx = match TransactionEnvelope::from_xdr_base64(&challenge.transaction) {
+        Ok(TransactionEnvelope::Transaction(tx)) => tx,
+        _ => return Err(anyhow!("Invalid challenge transaction")),
+    };
+    validate_challenge(
+        &tx,
+        &network,
+        &server_key,
+        keypair.public_key(),
+        &home_domain,
+        endpoint,
+    )?;
+    tx.sign(keypair, &network)?;
+    let body = serde_json::json!({ "transaction": tx.into_envelope().xdr_base64()? });
+    let response: TokenResponse = http::post_json(endpoint, None, RequestBody::Json(body)).await?;
+
+    Ok(AuthToken {
+        domain: home_domain,
+        account,
+        expires_at: token_expiration(&response.token),
+        token: response.token,
+    })
+}
+
+/// Checks that `tx` is a valid SEP-10 challenge for `account` from the
+/// server with `server_key`.
+fn validate_challenge(
+    tx: &Transaction,
+    network: &Network,
+    server_key: &PublicKey,
+    account: &PublicKey,
+    home_domain: &str,
+    endpoint: &str,
+) -> Result<()> {
+    if muxed_public_key(tx.source_account()) != *server_key {
+        return Err(anyhow!(
+            "The challenge source is not the server signing key"
+        ));
+    }
+    if *tx.sequence() != 0 {
+        return Err(anyhow!("The challenge sequence number is not zero"));
+    }
+    let now = Utc::now();
+    match tx.time_bounds() {
+        Some(time_bounds) => {
+            if time_bounds.lower().map(|l| l > now).unwrap_or(false) {
+                return Err(anyhow!("The challenge is not valid yet"));
+            }
+            if time_bounds.upper().map(|u| u < now).unwrap_or(true) {
+                return Err(anyhow!("The challenge has expired"));
+            }
+        }
+        None => return Err(anyhow!("The challenge has no time bounds")),
+    }
+
+    let auth_key = format!("{} auth", home_domain);
+    // The web_auth_domain is the host of the endpoint, with its port if any.
+    let endpoint_host = url::Url::parse(endpoint).ok().and_then(|url| {
+        let host = url.host_str()?;
+        Some(match url.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        })
+    });
+    for (index, operation) in tx.operations().iter().enumerate() {
+        let op = match operation {
+            Operation::ManageData(op) => op,
+            _ => {
+                return Err(anyhow!(
+                    "The challenge operation {} is not a manage data operation",
+                    index
+                ))
+            }
+        };
+        let source = op.source_account().as_ref().map(muxed_public_key);
+        if index == 0 {
+            if source.as_ref() != Some(account) {
+                return Err(anyhow!(
+                    "The challenge first operation source is not {}",
+                    account
+                ));
+            }
+            if op.data_name() != auth_key {
+                return Err(anyhow!(
+                    "The challenge is for '{}' instead of '{}'",
+                    op.data_name(),
+                    auth_key
+                ));
+            }
+            let nonce = op.data_value().as_ref().map(|v| v.as_bytes().len());
+            if nonce != Some(64) {
+                return Err(anyhow!("The challenge nonce is not 64 bytes long"));
+            }
+            continue;
+        }
+        if source.as_ref() != Some(server_key) {
+            return Err(anyhow!(
+                "The challenge operation {} source is not the server signing key",
+                index
+            ));
+        }
+        if op.data_name() == "web_auth_domain" {
+            let value = op.data_value().as_ref().map(|v| v.as_bytes());
+            if value != endpoint_host.as_ref().map(|h| h.as_bytes()) {
+                return Err(anyhow!(
+                    "The challenge web_auth_domain does not match {}",
+                    endpoint
+                ));
+            }
+        }
+    }
+    if tx.operations().is_empty() {
+        return Err(anyhow!("The challenge has no operations"));
+    }
+
+    let hash = tx.hash(network)?;
+    let hint = SignatureHint::from_public_key(server_key);
+    let signed = tx
+        .signatures()
+        .iter()
+        .any(|s| *s.hint() == hint && s.signature().verify(server_key, &hash));
+    if !signed {
+        return Err(anyhow!("The challenge is not signed by the server"));
+    }
+    Ok(())
+}
+
+/// Returns the expiration time of a JWT, read from its `exp` claim.
+fn token_expiration(token: &str) -> Option<DateTime<Utc>> {
+    let payload = token.split('.').nth(1)?;
+    let payload = base64::decode_config(payload, base64::URL_SAFE_NO_PAD).ok()?;
+    let claims: TokenClaims = serde_json::from_slice(&payload).ok()?;
+    Utc.timestamp_opt(claims.exp?, 0).single()
+}
+
+impl Render for AuthToken {
+    fn render_for_humans(
+        &self,
+        mut fmt: &mut convey::human::Formatter,
+    ) -> std::result::Result<(), convey::Error> {
+        let expires_at = match &self.expires_at {
+            Some(expires_at) => expires_at.to_string(),
+            None => "no expiration".to_string(),
+        };
+        text(&self.token).render_for_humans(&mut fmt)?;
+        text("\t").render_for_humans(&mut fmt)?;
+        text(expires_at).render_for_humans(&mut fmt)?;
+        newline().render_for_humans(&mut fmt)?;
+        Ok(())
+    }
+
+    render_json!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::TestServer;
+    use chrono::Duration;
+    use stellar_base::account::DataValue;
+    use stellar_base::time_bounds::TimeBounds;
+    use stellar_base::transaction::MIN_BASE_FEE;
+
+    const HOME_DOMAIN: &str = "testanchor.stellar.org";
+    const ENDPOINT: &str = "https://testanchor.stellar.org/auth";
+
+    struct Challenge {
+        server: KeyPair,
+        client: KeyPair,
+        source: PublicKey,
+        sequence: i64,
+        time_bounds: TimeBounds,
+        operations: Vec<Operation>,
+        signed: bool,
+    }
+
+    impl Challenge {
+        fn new() -> Challenge {
+            Challenge::for_domain(
+                KeyPair::random().unwrap(),
+                KeyPair::random().unwrap(),
+                HOME_DOMAIN,
+            )
+        }
+
+        fn for_domain(server: KeyPair, client: KeyPair, home_domain: &str) -> Challenge {
+            let nonce = base64::encode(stellar_base::crypto::random_bytes(48));
+            let operations = vec![
+                Operation::new_manage_data()
+                    .with_source_account(client.public_key().clone())
+                    .with_data_name(format!("{} auth", home_domain))
+                    .with_data_value(Some(DataValue::from_slice(nonce.as_bytes()).unwrap()))
+                    .build()
+                    .unwrap(),
+                Operation::new_manage_data()
+                    .with_source_account(server.public_key().clone())
+                    .with_data_name("web_auth_domain".to_string())
+                    .with_data_value(Some(DataValue::from_slice(home_domain.as_bytes()).unwrap()))
+                    .build()
+                    .unwrap(),
+            ];
+            Challenge {
+                source: server.public_key().clone(),
+                server,
+                client,
+                sequence: 0,
+                time_bounds: TimeBounds::valid_for(Duration::minutes(15)),
+                operations,
+                signed: true,
+            }
+        }
+
+        fn transaction(&self, network: &Network) -> Transaction {
+            let mut builder =
+                Transaction::builder(self.source.clone(), self.sequence, MIN_BASE_FEE)
+                    .with_time_bounds(self.time_bounds.clone());
+            for operation in &self.operations {
+                builder = builder.add_operation(operation.clone());
+            }
+            let mut tx = builder.into_transaction().unwrap();
+            if self.signed {
+                tx.sign(&self.server, network).unwrap();
+            }
+            tx
+        }
+
+        fn validate(self) -> Result<()> {
+            let network = Network::new_test();
+            let tx = self.transaction(&network);
+            validate_challenge(
+                &tx,
+                &network,
+                self.server.public_key(),
+                self.client.public_key(),
+                HOME_DOMAIN,
+                ENDPOINT,
+            )
+        }
+    }
+
+    fn error(challenge: Challenge) -> String {
+        challenge.validate().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn accepts_valid_challenge() {
+        Challenge::new().validate().unwrap();
+    }
+
+    #[test]
+    fn rejects_wrong_source() {
+        let mut challenge = Challenge::new();
+        challenge.source = challenge.client.public_key().clone();
+        assert_eq!(
+            error(challenge),
+            "The challenge source is not the server signing key"
+        );
+    }
+
+    #[test]
+    fn rejects_non_zero_sequence() {
+        let mut challenge = Challenge::new();
+        challenge.sequence = 1;
+        assert_eq!(
+            error(challenge),
+            "The challenge sequence number is not zero"
+        );
+    }
+
+    #[test]
+    fn rejects_expired_challenge() {
+        let mut challenge = Challenge::new();
+        challenge.time_bounds = TimeBounds::always_valid()
+            .with_upper(Utc::now() - Duration::minutes(1))
+            .unwrap();
+        assert_eq!(error(challenge), "The challenge has expired");
+    }
+
+    #[test]
+    fn rejects_short_nonce() {
+        let mut challenge = Challenge::new();
+        challenge.operations[0] = Operation::new_manage_data()
+            .with_source_account(challenge.client.public_key().clone())
+            .with_data_name(format!("{} auth", HOME_DOMAIN))
+            .with_data_value(Some(DataValue::from_slice(b"nonce").unwrap()))
+            .build()
+            .unwrap();
+        assert_eq!(error(challenge), "The challenge nonce is not 64 bytes long");
+    }
+
+    #[test]
+    fn rejects_foreign_operation() {
+        let mut challenge = Challenge::new();
+        challenge.operations.push(
+            Operation::new_bump_sequence()
+                .with_bump_to(1)
+                .build()
+                .unwrap(),
+        );
+        assert_eq!(
+            error(challenge),
+            "The challenge operation 2 is not a manage data operation"
+        );
+    }
+
+    #[test]
+    fn rejects_missing_server_signature() {
+        let mut challenge = Challenge::new();
+        challenge.signed = false;
+        assert_eq!(
+            error(challenge),
+            "The challenge is not signed by the server"
+        );
+    }
+
+    #[test]
+    fn reads_token_expiration() {
+        let claims = base64::encode_config(r#"{"exp":1700000000}"#, base64::URL_SAFE_NO_PAD);
+        let token = format!("header.{}.signature", claims);
+        assert_eq!(
+            token_expiration(&token),
+            Utc.timestamp_opt(1_700_000_000, 0).single()
+        );
+        assert_eq!(token_expiration("header.e30.signature"), None);
+        assert_eq!(token_expiration("not a token"), None);
+    }
+
+    #[tokio::test]
+    async fn requests_token_from_web_auth_endpoint() {
+        let server = KeyPair::random().unwrap();
+        let client = KeyPair::random().unwrap();
+        let server_key = server.public_key().account_id();
+        let client_key = client.public_key().clone();
+        let signer = client.clone();
+        let anchor = TestServer::start(move |url, request| {
+            let home_domain = stellar_toml::domain_host(url);
+            match (request.method.as_str(), request.path.as_str()) {
+                ("GET", "/.well-known/stellar.toml") => (
+                    200,
+                    format!(
+                        "NETWORK_PASSPHRASE = \"{}\"\nSIGNING_KEY = \"{}\"\nWEB_AUTH_ENDPOINT = \"{}/auth\"\n",
+                        Network::new_test().passphrase(),
+                        server_key,
+                        url
+                    ),
+                ),
+                ("GET", "/auth") => {
+                    assert_eq!(request.param("account"), Some(client_key.account_id().as_str()));
+                    let challenge =
+                        Challenge::for_domain(server.clone(), signer.clone(), home_domain);
+                    let tx = challenge.transaction(&Network::new_test());
+                    let xdr = tx.into_envelope().xdr_base64().unwrap();
+                    (200, serde_json::json!({ "transaction": xdr }).to_string())
+                }
+                ("POST", "/auth") => (200, r#"{"token": "header.e30.signature"}"#.to_string()),
+                _ => (404, "{}".to_string()),
+            }
+        })
+        .await;
+
+        let token = request_token(&anchor.url, &client).await.unwrap();
+        assert_eq!(token.token, "header.e30.signature");
+        assert_eq!(token.account, client.public_key().account_id());
+
+        let requests = anchor.requests();
+        let post = requests.iter().find(|r| r.method == "POST").unwrap();
+        let body: serde_json::Value = serde_json::from_str(&post.body).unwrap();
+        let envelope =
+            TransactionEnvelope::from_xdr_base64(body["transaction"].as_str().unwrap()).unwrap();
+        let tx = match envelope {
+            TransactionEnvelope::Transaction(tx) => tx,
+            _ => panic!("Expected a transaction envelope"),
+        };
+        let hash = tx.hash(&Network::new_test()).unwrap();
+        assert_eq!(tx.signatures().len(), 2);
+        assert!(tx
+            .signatures()
+            .iter()
+            .any(|s| s.signature().verify(client.public_key(), &hash)));
+    }
+}
@@ -0,0 +1,232 @@
+use crate::asset::parse_asset;
+use crate::config::AppConfig;
+use crate::predicate::{is_claimable, parse_predicate};
+use crate::wallet::{self, TransactionOptions};
+use anyhow::Result;
+use convey::Output;
+use std::str::FromStr;
+use stellar_base::amount::Amount;
+use stellar_base::claim::{ClaimPredicate, ClaimableBalanceId, Claimant};
+use stellar_base::xdr::MAX_OPS_PER_TX;
+use stellar_base::{Operation, PublicKey};
+use stellar_horizon::api;
+use stellar_horizon::client::HorizonClient;
+use stellar_horizon::request::PageRequest;
+use stellar_horizon::resources;
+use structopt::StructOpt;
+
+const CLAIMABLE_BALANCES_PAGE_LIMIT: u64 = 200;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Create and claim claimable balances")]
+pub struct ClaimableBalanceCommand {
+    #[structopt(short, long, global = true, help = "Which Horizon server to use")]
+    pub server: Option<String>,
+    #[structopt(subcommand)]
+    pub command: ClaimableBalanceInnerCommand,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum ClaimableBalanceInnerCommand {
+    #[structopt(about = "Creates a claimable balance")]
+    Create(CreateClaimableBalanceCommand),
+    #[structopt(about = "Claims a claimable balance")]
+    Claim(ClaimClaimableBalanceCommand),
+    #[structopt(about = "Claims all balances an account can claim right now")]
+    ClaimAll(ClaimAllClaimableBalanceCommand),
+}
+
+#[derive(Debug, StructOpt)]
+pub struct CreateClaimableBalanceCommand {
+    #[structopt(
+        name = "FROM",
+        help = "The sender account id, starts with G, or its name"
+    )]
+    pub from: String,
+    #[structopt(name = "ASSET", help = "The asset sent. Use XLM or CODE:ISSUER.")]
+    pub asset: String,
+    #[structopt(name = "AMOUNT", help = "The amount of ASSET sent")]
+    pub amount: String,
+    #[structopt(
+        long = "claimant",
+        required = true,
+        number_of_values = 1,
+        help = "A claimant as ACCOUNT[:PREDICATE], can be repeated. PREDICATE is unconditional, before(TIME), after(TIME), not(P), and(P, P) or or(P, P). TIME is YYYY-MM-DD, RFC 3339 or relative like +30d."
+    )]
+    pub claimants: Vec<String>,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ClaimClaimableBalanceCommand {
+    #[structopt(name = "CLAIMABLE_BALANCE_ID", help = "The claimable balance id")]
+    pub claimable_balance_id: String,
+    #[structopt(
+        long = "as",
+        help = "The claimant account id, starts with G, or its name"
+    )]
+    pub claimant: String,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ClaimAllClaimableBalanceCommand {
+    #[structopt(
+        long = "as",
+        help = "The claimant account id, starts with G, or its name"
+    )]
+    pub claimant: String,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+pub async fn run_command(
+    mut out: &mut Output,
+    config: &AppConfig,
+    command: ClaimableBalanceCommand,
+) -> Result<()> {
+    let client = wallet::horizon_client(&config, &command.server)?;
+    match command.command {
+        ClaimableBalanceInnerCommand::Create(cmd) => {
+            run_create(&mut out, &config, &client, cmd).await
+        }
+        ClaimableBalanceInnerCommand::Claim(cmd) => {
+            run_claim(&mut out, &config, &client, cmd).await
+        }
+        ClaimableBalanceInnerCommand::ClaimAll(cmd) => {
+            run_claim_all(&mut out, &config, &client, cmd).await
+        }
+    }
+}
+
+pub async fn run_create<H>(
+    mut out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: CreateClaimableBalanceCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let keypair = config.find_account(&command.from)?.keypair()?;
+    let mut builder = Operation::new_create_claimable_balance()
+        .with_asset(parse_asset(&command.asset)?)
+        .with_amount(Amount::from_str(&command.amount)?)?;
+    for claimant in &command.claimants {
+        builder = builder.add_claimant(parse_claimant(&config, claimant)?);
+    }
+    let operation = builder.build()?;
+    let tx = wallet::build_transaction(client, keypair.public_key(), vec![operation]).await?;
+    wallet::sign_and_submit(&mut out, client, &command.transaction, tx, &[keypair]).await
+}
+
+pub async fn run_claim<H>(
+    mut out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: ClaimClaimableBalanceCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let keypair = config.find_account(&command.claimant)?.keypair()?;
+    let operation = claim_operation(&command.claimable_balance_id)?;
+    let tx = wallet::build_transaction(client, keypair.public_key(), vec![operation]).await?;
+    wallet::sign_and_submit(&mut out, client, &command.transaction, tx, &[keypair]).await
+}
+
+pub async fn run_claim_all<H>(
+    mut out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: ClaimAllClaimableBalanceCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let keypair = config.find_account(&command.claimant)?.keypair()?;
+    let claimant = keypair.public_key();
+    let now = wallet::latest_ledger_close_time(client).await?;
+    let balances = all_by_claimant(client, claimant).await?;
+    let operations = balances
+        .iter()
+        .filter(|balance| {
+            balance
+                .claimants
+                .iter()
+                .any(|c| c.destination == claimant.account_id() && is_claimable(&c.predicate, &now))
+        })
+        .map(|balance| claim_operation(&balance.id))
+        .collect::<Result<Vec<_>>>()?;
+    if operations.is_empty() {
+        return Err(anyhow!("No claimable balances found"));
+    }
+    let account = wallet::load_account(client, claimant).await?;
+    let sequences = wallet::next_sequence(&account)?..;
+    for (batch, sequence) in operations.chunks(MAX_OPS_PER_TX as usize).zip(sequences) {
+        let tx = wallet::build_transaction_with_sequence(claimant, sequence, batch.to_vec())?;
+        let signers = std::slice::from_ref(&keypair);
+        wallet::sign_and_submit(&mut out, client, &command.transaction, tx, signers).await?;
+    }
+    Ok(())
+}
+
+pub async fn all_by_claimant<H>(
+    client: &H,
+    claimant: &PublicKey,
+) -> Result<Vec<resources::ClaimableBalance>>
+where
+    H: HorizonClient,
+{
+    let mut balances = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut request = api::claimable_balances::all_by_claimant(claimant)
+            .with_limit(CLAIMABLE_BALANCES_PAGE_LIMIT);
+        if let Some(cursor) = &cursor {
+            request = request.with_cursor(cursor);
+        }
+        let (_, response) = client.request(request).await?;
+        let last = match response.records.last() {
+            None => break,
+            Some(last) => last.paging_token.clone(),
+        };
+        let done = (response.records.len() as u64) < CLAIMABLE_BALANCES_PAGE_LIMIT;
+        balances.extend(response.records);
+        if done {
+            break;
+        }
+        cursor = Some(last);
+    }
+    Ok(balances)
+}
+
+/// Parses a claimable balance id as returned by Horizon, that is the hex
+/// encoded balance id type followed by the hash.
+pub fn parse_balance_id(balance_id: &str) -> Result<ClaimableBalanceId> {
+    let hash = match balance_id.len() {
+        64 => balance_id,
+        72 => &balance_id[8..],
+        _ => return Err(anyhow!("Invalid claimable balance id")),
+    };
+    let hash = hex::decode(hash).map_err(|_| anyhow!("Invalid claimable balance id"))?;
+    Ok(ClaimableBalanceId::new(hash)?)
+}
+
+fn claim_operation(balance_id: &str) -> Result<Operation> {
+    Ok(Operation::new_claim_claimable_balance()
+        .with_claimable_balance_id(parse_balance_id(balance_id)?)
+        .build()?)
+}
+
+fn parse_claimant(config: &AppConfig, claimant: &str) -> Result<Claimant> {
+    let mut parts = claimant.splitn(2, ':');
+    let destination = config.resolve_public_key(parts.next().unwrap_or_default())?;
+    let predicate = match parts.next() {
+        None => ClaimPredicate::new_unconditional(),
+        Some(predicate) => parse_predicate(predicate)?,
+    };
+    Ok(Claimant::new(destination, predicate))
+}
@@ -1,5 +1,18 @@
 use crate::account;
+use crate::anchor;
+use crate::asset;
+use crate::auth;
+use crate::claimable_balance;
+use crate::data;
 use crate::horizon;
+use crate::offer;
+use crate::pay;
+use crate::pool;
+use crate::sponsor;
+use crate::trust;
+use crate::tx;
+use crate::uri;
+use crate::xdr;
 use anyhow::Result;
 use convey::Output;
 use structopt::StructOpt;
@@ -37,7 +50,20 @@ pub struct Aurora {
 #[derive(Debug, StructOpt)]
 pub enum Command {
     Account(account::AccountCommand),
+    Anchor(anchor::AnchorCommand),
+    Asset(asset::AssetCommand),
+    Auth(auth::AuthCommand),
+    ClaimableBalance(claimable_balance::ClaimableBalanceCommand),
+    Data(data::DataCommand),
     Horizon(horizon::HorizonCommand),
+    Offer(offer::OfferCommand),
+    Pay(pay::PayCommand),
+    Pool(pool::PoolCommand),
+    Sponsor(sponsor::SponsorCommand),
+    Trust(trust::TrustCommand),
+    Tx(tx::TxCommand),
+    Uri(uri::UriCommand),
+    Xdr(xdr::XdrCommand),
 }
 
 pub async fn run_command(
@@ -46,7 +72,22 @@ pub async fn run_command(
     command: Command,
 ) -> Result<()> {
     match command {
-        Command::Account(cmd) => account::run_command(&mut out, &mut config, cmd),
+        Command::Account(cmd) => account::run_command(&mut out, &mut config, cmd).await,
+        Command::Anchor(cmd) => anchor::run_command(&mut out, &config, cmd).await,
+        Command::Asset(cmd) => asset::run_command(&mut out, &config, cmd).await,
+        Command::Auth(cmd) => auth::run_command(&mut out, &config, cmd).await,
+        Command::ClaimableBalance(cmd) => {
+            claimable_balance::run_command(&mut out, &config, cmd).await
+        }
+        Command::Data(cmd) => data::run_command(&mut out, &config, cmd).await,
         Command::Horizon(cmd) => horizon::run_command(&mut out, &mut config, cmd).await,
+        Command::Offer(cmd) => offer::run_command(&mut out, &config, cmd).await,
+        Command::Pay(cmd) => pay::run_command(&mut out, &config, cmd).await,
+        Command::Pool(cmd) => pool::run_command(&mut out, &config, cmd).await,
+        Command::Sponsor(cmd) => sponsor::run_command(&mut out, &config, cmd).await,
+        Command::Trust(cmd) => trust::run_command(&mut out, &config, cmd).await,
+        Command::Tx(cmd) => tx::run_command(&mut out, &config, cmd).await,
+        Command::Uri(cmd) => uri::run_command(&mut out, &config, cmd).await,
+        Command::Xdr(cmd) => xdr::run_command(&mut out, &config, cmd),
     }
 }
@@ -1,7 +1,7 @@
 use anyhow::Result;
 use convey::components::{newline, text};
 use convey::Render;
-use stellar_base::KeyPair;
+use stellar_base::{KeyPair, PublicKey};
 use stellar_horizon::client::HorizonHttpClient;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -15,6 +15,8 @@ pub struct AppConfig {
 pub struct Account {
     pub account_id: String,
     pub secret_seed: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -54,6 +56,20 @@ impl AppConfig {
             .ok_or_else(|| anyhow!("Invalid server name"))?;
         Ok(HorizonHttpClient::new_from_str(&server.uri)?)
     }
+
+    pub fn find_account(&self, account: &str) -> Result<&Account> {
+        self.accounts
+            .iter()
+            .find(|a| a.account_id == account || a.name.as_deref() == Some(account))
+            .ok_or_else(|| anyhow!("Account {} not found", account))
+    }
+
+    pub fn resolve_public_key(&self, account: &str) -> Result<PublicKey> {
+        match self.find_account(account) {
+            Ok(account) => Ok(PublicKey::from_account_id(&account.account_id)?),
+            Err(_) => Ok(PublicKey::from_account_id(account)?),
+        }
+    }
 }
 
 impl Account {
@@ -61,8 +77,13 @@ impl Account {
         Account {
             account_id: keypair.public_key().account_id(),
             secret_seed: keypair.secret_key().secret_seed(),
+            name: None,
         }
     }
+
+    pub fn keypair(&self) -> Result<KeyPair> {
+        Ok(KeyPair::from_secret_seed(&self.secret_seed)?)
+    }
 }
 
 impl Server {
@@ -76,7 +97,8 @@ impl Server {
 
 impl Render for Account {
     render_for_humans!(self -> [
-        text(&self.account_id), text("\t"), text(&self.secret_seed), newline(),
+        text(&self.account_id), text("\t"), text(&self.secret_seed), text("\t"),
+        text(self.name.as_deref().unwrap_or("-")), newline(),
     ]);
 
     render_json!();
@@ -0,0 +1,280 @@
+use crate::config::AppConfig;
+use crate::error::Error;
+use crate::wallet::{self, TransactionOptions};
+use anyhow::Result;
+use convey::components::{newline, text};
+use convey::{Output, Render};
+use stellar_base::account::DataValue;
+use stellar_base::Operation;
+use stellar_horizon::client::HorizonClient;
+use stellar_horizon::error::Error as HorizonError;
+use stellar_horizon::horizon_error::HorizonError as HorizonRequestError;
+use stellar_horizon::request::Request;
+use stellar_horizon::resources;
+use structopt::StructOpt;
+use url::Url;
+
+const MAX_DATA_LENGTH: usize = 64;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Manage account data entries")]
+pub struct DataCommand {
+    #[structopt(short, long, global = true, help = "Which Horizon server to use")]
+    pub server: Option<String>,
+    #[structopt(subcommand)]
+    pub command: DataInnerCommand,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum DataInnerCommand {
+    #[structopt(about = "Adds or updates a data entry")]
+    Set(SetDataCommand),
+    #[structopt(about = "Removes a data entry")]
+    Delete(DeleteDataCommand),
+    #[structopt(about = "Shows the decoded value of a data entry")]
+    Get(GetDataCommand),
+}
+
+#[derive(Debug, StructOpt)]
+pub struct SetDataCommand {
+    #[structopt(
+        name = "ACCOUNT_ID",
+        help = "The account id, starts with G, or its name"
+    )]
+    pub account_id: String,
+    #[structopt(name = "KEY", help = "The data entry name, up to 64 bytes")]
+    pub key: String,
+    #[structopt(
+        name = "VALUE",
+        required_unless = "file",
+        help = "The data entry value, up to 64 bytes"
+    )]
+    pub value: Option<String>,
+    #[structopt(
+        long,
+        conflicts_with = "VALUE",
+        help = "Read the data entry value from this file"
+    )]
+    pub file: Option<String>,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct DeleteDataCommand {
+    #[structopt(
+        name = "ACCOUNT_ID",
+        help = "The account id, starts with G, or its name"
+    )]
+    pub account_id: String,
+    #[structopt(name = "KEY", help = "The data entry name")]
+    pub key: String,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct GetDataCommand {
+    #[structopt(
+        name = "ACCOUNT_ID",
+        help = "The account id, starts with G, or its name"
+    )]
+    pub account_id: String,
+    #[structopt(name = "KEY", help = "The data entry name")]
+    pub key: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DataEntry {
+    pub name: String,
+    /// The value if it is valid UTF-8.
+    pub value: Option<String>,
+    pub base64: String,
+    pub sponsor: Option<String>,
+}
+
+/// Horizon request for a single data entry of an account.
+/// Unlike `api::data::for_account`, it percent-encodes the key in the path.
+struct AccountDataRequest {
+    account_id: String,
+    key: String,
+}
+
+impl Request for AccountDataRequest {
+    type Response = resources::AccountData;
+
+    fn uri(&self, host: &Url) -> stellar_horizon::error::Result<Url> {
+        let mut url = host.join(&format!("/accounts/{}/data", self.account_id))?;
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments.push(&self.key);
+        }
+        Ok(url)
+    }
+}
+
+pub async fn run_command(out: &mut Output, config: &AppConfig, command: DataCommand) -> Result<()> {
+    let client = wallet::horizon_client(&config, &command.server)?;
+    match command.command {
+        DataInnerCommand::Set(cmd) => run_set(out, config, &client, cmd).await,
+        DataInnerCommand::Delete(cmd) => run_delete(out, config, &client, cmd).await,
+        DataInnerCommand::Get(cmd) => run_get(out, config, &client, cmd).await,
+    }
+}
+
+async fn run_set<H>(
+    out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: SetDataCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    check_key(&command.key)?;
+    let value = match (&command.value, &command.file) {
+        (_, Some(file)) => {
+            std::fs::read(file).map_err(|err| anyhow!("Could not read {}: {}", file, err))?
+        }
+        (Some(value), None) => value.as_bytes().to_vec(),
+        (None, None) => return Err(anyhow!("Missing VALUE or --file")),
+    };
+    if value.len() > MAX_DATA_LENGTH {
+        return Err(anyhow!(
+            "The data entry value is {} bytes long, the maximum is {}",
+            value.len(),
+            MAX_DATA_LENGTH
+        ));
+    }
+    let operation = Operation::new_manage_data()
+        .with_data_name(command.key.clone())
+        .with_data_value(Some(DataValue::from_slice(&value)?))
+        .build()?;
+    submit_operation(
+        out,
+        config,
+        client,
+        &command.account_id,
+        operation,
+        &command.transaction,
+    )
+    .await
+}
+
+async fn run_delete<H>(
+    out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: DeleteDataCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    check_key(&command.key)?;
+    let account = config.resolve_public_key(&command.account_id)?;
+    let account = wallet::load_account(client, &account).await?;
+    if !account.data.contains_key(&command.key) {
+        return Err(anyhow!(
+            "Account {} has no data entry {}",
+            command.account_id,
+            command.key
+        ));
+    }
+    let operation = Operation::new_manage_data()
+        .with_data_name(command.key.clone())
+        .with_data_value(None)
+        .build()?;
+    submit_operation(
+        out,
+        config,
+        client,
+        &command.account_id,
+        operation,
+        &command.transaction,
+    )
+    .await
+}
+
+async fn run_get<H>(
+    out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: GetDataCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let account = config.resolve_public_key(&command.account_id)?;
+    let request = AccountDataRequest {
+        account_id: account.account_id(),
+        key: command.key.clone(),
+    };
+    let data = match client.request(request).await {
+        Ok((_, data)) => data,
+        Err(HorizonError::HorizonRequestError(HorizonRequestError::Other(err)))
+            if err.status == 404 =>
+        {
+            return Err(anyhow!(
+                "Account {} has no data entry {}",
+                command.account_id,
+                command.key
+            ))
+        }
+        Err(err) => Err(err)?,
+    };
+    let bytes = base64::decode(&data.value)
+        .map_err(|_| anyhow!("Invalid data entry value {}", data.value))?;
+    let entry = DataEntry {
+        name: command.key,
+        value: String::from_utf8(bytes).ok(),
+        base64: data.value,
+        sponsor: data.sponsor,
+    };
+    out.print(entry).map_err(Error::Convey)?;
+    Ok(())
+}
+
+async fn submit_operation<H>(
+    mut out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    account_id: &str,
+    operation: Operation,
+    options: &TransactionOptions,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let keypair = config.find_account(account_id)?.keypair()?;
+    let tx = wallet::build_transaction(client, keypair.public_key(), vec![operation]).await?;
+    wallet::sign_and_submit(&mut out, client, options, tx, &[keypair]).await
+}
+
+fn check_key(key: &str) -> Result<()> {
+    if key.is_empty() || key.len() > MAX_DATA_LENGTH {
+        return Err(anyhow!(
+            "The data entry name must be 1 to {} bytes long",
+            MAX_DATA_LENGTH
+        ));
+    }
+    Ok(())
+}
+
+impl Render for DataEntry {
+    fn render_for_humans(
+        &self,
+        mut fmt: &mut convey::human::Formatter,
+    ) -> std::result::Result<(), convey::Error> {
+        // Binary values are shown base64 encoded, as Horizon returns them.
+        let value = match &self.value {
+            Some(value) => value.clone(),
+            None => format!("base64:{}", self.base64),
+        };
+        text(&self.name).render_for_humans(&mut fmt)?;
+        text("\t").render_for_humans(&mut fmt)?;
+        text(value).render_for_humans(&mut fmt)?;
+        newline().render_for_humans(&mut fmt)?;
+        Ok(())
+    }
+
+    render_json!();
+}
@@ -0,0 +1,159 @@
+use crate::error::Error;
+use crate::wallet::TransactionOptions;
+use anyhow::Result;
+use convey::components::{newline, text};
+use convey::{Output, Render};
+use std::str::FromStr;
+use stellar_base::amount::Stroops;
+use stellar_base::transaction::{Transaction, MIN_BASE_FEE};
+use stellar_horizon::api;
+use stellar_horizon::client::HorizonClient;
+use stellar_horizon::resources::{FeeDistribution, FeeStats};
+
+/// The fee cap per operation in stroops used with a fee strategy when there
+/// is no `--fee-cap`, so that a fee surge does not spend without limit.
+pub const DEFAULT_FEE_CAP: i64 = 10_000;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SurgeWarning {
+    pub base_fee: i64,
+    pub surge_fee: i64,
+}
+
+/// How to choose the base fee of a transaction, that is its fee per
+/// operation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeeStrategy {
+    /// A percentile of the fees charged in the last ledgers.
+    Percentile(u8),
+    /// A fixed base fee in stroops.
+    Max(i64),
+}
+
+impl FromStr for FeeStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(strategy: &str) -> Result<FeeStrategy> {
+        let invalid = || {
+            anyhow!(
+                "Invalid fee strategy {}, use p10 to p90, p95, p99 or max:N",
+                strategy
+            )
+        };
+        if let Some(fee) = strategy.strip_prefix("max:") {
+            let fee = fee.parse().map_err(|_| invalid())?;
+            return Ok(FeeStrategy::Max(fee));
+        }
+        let percentile = strategy
+            .strip_prefix('p')
+            .and_then(|p| p.parse().ok())
+            .ok_or_else(invalid)?;
+        match percentile {
+            10 | 20 | 30 | 40 | 50 | 60 | 70 | 80 | 90 | 95 | 99 => {
+                Ok(FeeStrategy::Percentile(percentile))
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl FeeStrategy {
+    pub fn base_fee(&self, stats: &FeeStats) -> i64 {
+        match self {
+            FeeStrategy::Max(fee) => *fee,
+            FeeStrategy::Percentile(percentile) => percentile_fee(&stats.fee_charged, *percentile),
+        }
+    }
+}
+
+/// Sets the fee of `tx` as chosen by the fee strategy and cap of `options`,
+/// leaving it unchanged if neither is given.
+pub async fn apply_fee_strategy<H>(
+    out: &mut Output,
+    client: &H,
+    options: &TransactionOptions,
+    tx: &mut Transaction,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    if options.fee_strategy.is_none() && options.fee_cap.is_none() {
+        return Ok(());
+    }
+    let operations = tx.operations().len().max(1) as i64;
+    let base_fee = choose_base_fee(out, client, options, tx.fee().to_i64() / operations).await?;
+    *tx.fee_mut() = Stroops::new(base_fee * operations);
+    Ok(())
+}
+
+/// Returns the base fee chosen by the fee strategy and cap of `options`,
+/// starting from `base_fee` if there is no strategy.
+pub async fn choose_base_fee<H>(
+    out: &mut Output,
+    client: &H,
+    options: &TransactionOptions,
+    base_fee: i64,
+) -> Result<i64>
+where
+    H: HorizonClient,
+{
+    let (_, stats) = client.request(api::aggregations::fee_stats()).await?;
+    let mut base_fee = match options.fee_strategy {
+        Some(strategy) => strategy.base_fee(&stats),
+        None => base_fee,
+    };
+    base_fee = base_fee.max(MIN_BASE_FEE.to_i64());
+    let cap = match options.fee_strategy {
+        Some(_) => Some(options.fee_cap.unwrap_or(DEFAULT_FEE_CAP)),
+        None => options.fee_cap,
+    };
+    if let Some(cap) = cap {
+        if cap < MIN_BASE_FEE.to_i64() {
+            return Err(anyhow!(
+                "The fee cap {} is below the minimum base fee {}",
+                cap,
+                MIN_BASE_FEE.to_i64()
+            ));
+        }
+        base_fee = base_fee.min(cap);
+    }
+    // Ledgers are full and fees above the network base fee are being
+    // charged, a lower fee may never be included.
+    let surge_fee = stats.fee_charged.min;
+    if surge_fee > stats.last_ledger_base_fee && base_fee < surge_fee {
+        out.print(SurgeWarning {
+            base_fee,
+            surge_fee,
+        })
+        .map_err(Error::Convey)?;
+    }
+    Ok(base_fee)
+}
+
+fn percentile_fee(fees: &FeeDistribution, percentile: u8) -> i64 {
+    match percentile {
+        10 => fees.p10,
+        20 => fees.p20,
+        30 => fees.p30,
+        40 => fees.p40,
+        50 => fees.p50,
+        60 => fees.p60,
+        70 => fees.p70,
+        80 => fees.p80,
+        90 => fees.p90,
+        95 => fees.p95,
+        _ => fees.p99,
+    }
+}
+
+impl Render for SurgeWarning {
+    render_for_humans!(self -> [
+        text(format!(
+            "Warning: the base fee {} is below the current surge pricing fee {}",
+            self.base_fee, self.surge_fee
+        )),
+        newline(),
+    ]);
+
+    render_json!();
+}
@@ -1,11 +1,18 @@
+use crate::asset::parse_asset;
+use crate::claimable_balance;
 use crate::config::AppConfig;
 use crate::error::Error;
+use crate::predicate::{describe_predicate, is_claimable};
 use crate::render::ResponseRender;
+use crate::wallet;
 use anyhow::Result;
-use convey::Output;
-use stellar_base::{Asset, PublicKey};
+use chrono::{DateTime, Utc};
+use convey::components::{newline, text};
+use convey::{Output, Render};
+use stellar_base::PublicKey;
 use stellar_horizon::api;
 use stellar_horizon::client::HorizonClient;
+use stellar_horizon::resources;
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
@@ -35,6 +42,11 @@ pub struct AllByClaimantClaimableBalanceCommand {
         help = "The claimable balance claimant account id"
     )]
     pub claimant_id: String,
+    #[structopt(
+        long,
+        help = "Only include balances the claimant can claim at the latest ledger close time"
+    )]
+    pub claimable_now: bool,
 }
 
 #[derive(Debug, StructOpt)]
@@ -53,6 +65,27 @@ pub struct SingleClaimableBalanceCommand {
     pub claimable_balance_id: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClaimantSummary {
+    pub destination: String,
+    pub predicate: String,
+    pub claimable_now: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClaimableBalanceSummary {
+    pub id: String,
+    pub asset: String,
+    pub amount: String,
+    pub claimants: Vec<ClaimantSummary>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClaimableBalanceList {
+    pub ledger_close_time: String,
+    pub claimable_balances: Vec<ClaimableBalanceSummary>,
+}
+
 pub async fn run_command<H>(
     mut out: &mut Output,
     config: &AppConfig,
@@ -85,7 +118,7 @@ pub async fn run_all_by_asset<H>(
 where
     H: HorizonClient,
 {
-    let asset = parse_asset(command.asset)?;
+    let asset = parse_asset(&command.asset)?;
     let request = api::claimable_balances::all_by_asset(asset);
     let (_, response) = client.request(request).await?;
     out.print(ResponseRender(response)).map_err(Error::Convey)?;
@@ -102,9 +135,19 @@ where
     H: HorizonClient,
 {
     let claimant = PublicKey::from_account_id(&command.claimant_id)?;
-    let request = api::claimable_balances::all_by_claimant(&claimant);
-    let (_, response) = client.request(request).await?;
-    out.print(ResponseRender(response)).map_err(Error::Convey)?;
+    let balances = claimable_balance::all_by_claimant(client, &claimant).await?;
+    let close_time = wallet::latest_ledger_close_time(client).await?;
+    let claimant_id = claimant.account_id();
+    let claimable_balances = balances
+        .iter()
+        .map(|balance| ClaimableBalanceSummary::new(balance, &close_time))
+        .filter(|balance| !command.claimable_now || balance.is_claimable_by(&claimant_id))
+        .collect();
+    let list = ClaimableBalanceList {
+        ledger_close_time: close_time.to_rfc3339(),
+        claimable_balances,
+    };
+    out.print(list).map_err(Error::Convey)?;
     Ok(())
 }
 
@@ -139,17 +182,82 @@ where
     Ok(())
 }
 
-fn parse_asset(asset: String) -> Result<Asset> {
-    let parts: Vec<&str> = asset.split(":").collect();
-    if parts.len() == 1 {
-        if parts[0].to_ascii_uppercase() == "XLM" {
-            return Ok(Asset::new_native());
+impl ClaimableBalanceSummary {
+    pub fn new(balance: &resources::ClaimableBalance, time: &DateTime<Utc>) -> Self {
+        let claimants = balance
+            .claimants
+            .iter()
+            .map(|claimant| ClaimantSummary {
+                destination: claimant.destination.clone(),
+                predicate: describe_predicate(&claimant.predicate),
+                claimable_now: is_claimable(&claimant.predicate, time),
+            })
+            .collect();
+        ClaimableBalanceSummary {
+            id: balance.id.clone(),
+            asset: balance.asset.clone(),
+            amount: balance.amount.clone(),
+            claimants,
         }
     }
-    if parts.len() == 2 {
-        let issuer = PublicKey::from_account_id(parts[1])?;
-        let result = Asset::new_credit(parts[0], issuer)?;
-        return Ok(result);
+
+    pub fn is_claimable_by(&self, account_id: &str) -> bool {
+        self.claimants
+            .iter()
+            .any(|c| c.destination == account_id && c.claimable_now)
     }
-    return Err(anyhow!("Invalid asset format"));
+}
+
+impl ClaimantSummary {
+    fn status(&self) -> &'static str {
+        if self.claimable_now {
+            "claimable now"
+        } else {
+            "not claimable now"
+        }
+    }
+}
+
+impl Render for ClaimantSummary {
+    render_for_humans!(self -> [
+        text("\t"), text(&self.destination), text("\t"),
+        text("claimable "), text(&self.predicate), text("\t"),
+        text(self.status()), newline(),
+    ]);
+
+    render_json!();
+}
+
+impl Render for ClaimableBalanceSummary {
+    fn render_for_humans(
+        &self,
+        mut fmt: &mut convey::human::Formatter,
+    ) -> std::result::Result<(), convey::Error> {
+        text(&self.id).render_for_humans(&mut fmt)?;
+        text("\t").render_for_humans(&mut fmt)?;
+        text(&self.amount).render_for_humans(&mut fmt)?;
+        text("\t").render_for_humans(&mut fmt)?;
+        text(&self.asset).render_for_humans(&mut fmt)?;
+        newline().render_for_humans(&mut fmt)?;
+        for claimant in &self.claimants {
+            claimant.render_for_humans(&mut fmt)?;
+        }
+        Ok(())
+    }
+
+    render_json!();
+}
+
+impl Render for ClaimableBalanceList {
+    fn render_for_humans(
+        &self,
+        mut fmt: &mut convey::human::Formatter,
+    ) -> std::result::Result<(), convey::Error> {
+        for balance in &self.claimable_balances {
+            balance.render_for_humans(&mut fmt)?;
+        }
+        Ok(())
+    }
+
+    render_json!();
 }
@@ -4,6 +4,7 @@ use crate::render::ResponseRender;
 use anyhow::Result;
 use convey::Output;
 use serde::ser::Serialize;
+use serde_json::Value;
 use stellar_horizon::api;
 use stellar_horizon::client::HorizonClient;
 use stellar_horizon::error::Error as HorizonError;
@@ -16,6 +17,7 @@ mod claimable_balance;
 mod effect;
 mod ledger;
 mod operation;
+mod path;
 mod payment;
 mod server;
 mod trade;
@@ -61,10 +63,13 @@ pub enum HorizonNonServerCommand {
     ClaimableBalance(claimable_balance::ClaimableBalanceCommand),
     Ledger(ledger::LedgerCommand),
     Operation(operation::OperationCommand),
+    Path(path::PathCommand),
     Payment(payment::PaymentCommand),
     Transaction(transaction::TransactionCommand),
     Effect(effect::EffectCommand),
     Trade(trade::TradeCommand),
+    #[structopt(about = "Retrieves the fees charged in the last ledgers")]
+    FeeStats,
     Info,
 }
 
@@ -91,6 +96,9 @@ pub async fn run_command(
                 HorizonNonServerCommand::Operation(cmd) => {
                     operation::run_command(&mut out, &config, &client, cmd).await
                 }
+                HorizonNonServerCommand::Path(cmd) => {
+                    path::run_command(&mut out, &config, &client, cmd).await
+                }
                 HorizonNonServerCommand::Payment(cmd) => {
                     payment::run_command(&mut out, &config, &client, cmd).await
                 }
@@ -103,6 +111,10 @@ pub async fn run_command(
                 HorizonNonServerCommand::Trade(cmd) => {
                     trade::run_command(&mut out, &config, &client, cmd).await
                 }
+                HorizonNonServerCommand::FeeStats => {
+                    let request = api::aggregations::fee_stats();
+                    execute_and_print_request(&mut out, &client, request).await
+                }
                 HorizonNonServerCommand::Info => {
                     execute_and_print_request(&mut out, &client, api::root::root()).await
                 }
@@ -191,3 +203,65 @@ where
         execute_and_print_request(&mut out, client, request).await
     }
 }
+
+/// Like `execute_and_print_request`, but applies `transform` to the JSON
+/// response before printing it.
+pub async fn execute_and_print_transformed_request<H, R, F>(
+    out: &mut Output,
+    client: &H,
+    request: R,
+    transform: F,
+) -> Result<()>
+where
+    H: HorizonClient,
+    R: Request,
+    R::Response: Serialize,
+    F: Fn(&mut Value) -> Result<()>,
+{
+    match client.request(request).await {
+        Ok((_, response)) => {
+            let mut response = serde_json::to_value(response)?;
+            transform(&mut response)?;
+            out.print(ResponseRender(response)).map_err(Error::Convey)?;
+            Ok(())
+        }
+        Err(HorizonError::HorizonRequestError(err)) => {
+            out.print(ResponseRender(err.clone()))
+                .map_err(Error::Convey)?;
+            Err(HorizonError::HorizonRequestError(err))?
+        }
+        Err(err) => Err(err)?,
+    }
+}
+
+/// Like `execute_and_print_stream_request`, but applies `transform` to the
+/// JSON response or events before printing them.
+pub async fn execute_and_print_transformed_stream_request<H, R, F>(
+    mut out: &mut Output,
+    client: &H,
+    mut request: R,
+    paging: &Paging,
+    streaming: &Streaming,
+    transform: F,
+) -> Result<()>
+where
+    H: HorizonClient,
+    R: StreamRequest + PageRequest + 'static,
+    R::Response: Serialize,
+    R::Resource: Serialize,
+    F: Fn(&mut Value) -> Result<()>,
+{
+    request = add_paging_options(request, &paging);
+
+    if streaming.stream {
+        let mut stream = client.stream(request)?;
+        while let Some(event) = stream.try_next().await? {
+            let mut event = serde_json::to_value(event)?;
+            transform(&mut event)?;
+            out.print(ResponseRender(event)).map_err(Error::Convey)?;
+        }
+        Ok(())
+    } else {
+        execute_and_print_transformed_request(&mut out, client, request, transform).await
+    }
+}
@@ -0,0 +1,137 @@
+use crate::asset::parse_asset;
+use crate::config::AppConfig;
+use crate::horizon::execute_and_print_request;
+use anyhow::Result;
+use convey::Output;
+use std::str::FromStr;
+use stellar_base::amount::Amount;
+use stellar_base::asset::CreditAsset;
+use stellar_base::PublicKey;
+use stellar_horizon::api;
+use stellar_horizon::client::HorizonClient;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Horizon payment path endpoints")]
+pub enum PathCommand {
+    StrictSend(StrictSendPathCommand),
+    StrictReceive(StrictReceivePathCommand),
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Retrieves the paths to send an exact amount of an asset")]
+pub struct StrictSendPathCommand {
+    #[structopt(
+        name = "SOURCE_ASSET",
+        help = "The asset sent. Use XLM for the native asset, and CODE:ISSUER for all other assets."
+    )]
+    pub source_asset: String,
+    #[structopt(name = "SOURCE_AMOUNT", help = "The amount of the asset sent")]
+    pub source_amount: String,
+    #[structopt(
+        long,
+        help = "Only include paths to assets this account can receive",
+        required_unless = "destination-asset"
+    )]
+    pub destination_account: Option<String>,
+    #[structopt(
+        long,
+        help = "Only include paths to this asset, can be repeated. Use CODE:ISSUER."
+    )]
+    pub destination_asset: Vec<String>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Retrieves the paths to receive an exact amount of an asset")]
+pub struct StrictReceivePathCommand {
+    #[structopt(
+        name = "DESTINATION_ASSET",
+        help = "The asset received. Use XLM for the native asset, and CODE:ISSUER for all other assets."
+    )]
+    pub destination_asset: String,
+    #[structopt(name = "DESTINATION_AMOUNT", help = "The amount of the asset received")]
+    pub destination_amount: String,
+    #[structopt(
+        long,
+        help = "Only include paths from assets this account holds",
+        required_unless = "source-asset"
+    )]
+    pub source_account: Option<String>,
+    #[structopt(
+        long,
+        help = "Only include paths from this asset, can be repeated. Use CODE:ISSUER."
+    )]
+    pub source_asset: Vec<String>,
+}
+
+pub async fn run_command<H>(
+    mut out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: PathCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    match command {
+        PathCommand::StrictSend(cmd) => run_strict_send(&mut out, &config, client, cmd).await,
+        PathCommand::StrictReceive(cmd) => run_strict_receive(&mut out, &config, client, cmd).await,
+    }
+}
+
+pub async fn run_strict_send<H>(
+    mut out: &mut Output,
+    _config: &AppConfig,
+    client: &H,
+    command: StrictSendPathCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let source_asset = parse_asset(&command.source_asset)?;
+    let source_amount = Amount::from_str(&command.source_amount)?;
+    let destination_assets = parse_credit_assets(&command.destination_asset)?;
+    let mut request =
+        api::aggregations::paths_strict_send(source_asset, destination_assets, source_amount)?;
+    if let Some(account_id) = command.destination_account {
+        let account = PublicKey::from_account_id(&account_id)?;
+        request = request.with_destination_account(&account);
+    }
+    execute_and_print_request(&mut out, client, request).await
+}
+
+pub async fn run_strict_receive<H>(
+    mut out: &mut Output,
+    _config: &AppConfig,
+    client: &H,
+    command: StrictReceivePathCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let destination_asset = parse_asset(&command.destination_asset)?;
+    let destination_amount = Amount::from_str(&command.destination_amount)?;
+    let source_assets = parse_credit_assets(&command.source_asset)?;
+    let mut request = api::aggregations::paths_strict_receive(
+        source_assets,
+        destination_asset,
+        destination_amount,
+    )?;
+    if let Some(account_id) = command.source_account {
+        let account = PublicKey::from_account_id(&account_id)?;
+        request = request.with_source_account(&account);
+    }
+    execute_and_print_request(&mut out, client, request).await
+}
+
+fn parse_credit_assets(assets: &[String]) -> Result<Vec<CreditAsset>> {
+    assets
+        .iter()
+        .map(|asset| {
+            parse_asset(asset)?
+                .as_credit()
+                .cloned()
+                .ok_or_else(|| anyhow!("The native asset cannot be used as a path filter"))
+        })
+        .collect()
+}
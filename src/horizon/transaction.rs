@@ -1,9 +1,12 @@
 use crate::config::AppConfig;
 use crate::horizon::{
-    execute_and_print_request, execute_and_print_stream_request, Paging, Streaming,
+    execute_and_print_transformed_request, execute_and_print_transformed_stream_request, Paging,
+    Streaming,
 };
+use crate::xdr::decode_transaction_xdr;
 use anyhow::Result;
 use convey::Output;
+use serde_json::Value;
 use stellar_base::PublicKey;
 use stellar_horizon::api;
 use stellar_horizon::client::HorizonClient;
@@ -24,6 +27,8 @@ pub enum TransactionCommand {
 pub struct SingleTransactionCommand {
     #[structopt(name = "TRANSACTION_ID", help = "The transaction id")]
     pub transaction_id: String,
+    #[structopt(long, help = "Include the decoded envelope, result and meta XDR")]
+    pub decode_xdr: bool,
 }
 
 #[derive(Debug, StructOpt)]
@@ -31,6 +36,8 @@ pub struct SingleTransactionCommand {
 pub struct AllTransactionsCommand {
     #[structopt(long, help = "Include failed transactions")]
     pub include_failed: bool,
+    #[structopt(long, help = "Include the decoded envelope, result and meta XDR")]
+    pub decode_xdr: bool,
     #[structopt(flatten)]
     pub paging: Paging,
     #[structopt(flatten)]
@@ -44,6 +51,8 @@ pub struct TransactionsForAccountCommand {
     pub account_id: String,
     #[structopt(long, help = "Include failed transactions")]
     pub include_failed: bool,
+    #[structopt(long, help = "Include the decoded envelope, result and meta XDR")]
+    pub decode_xdr: bool,
     #[structopt(flatten)]
     pub paging: Paging,
     #[structopt(flatten)]
@@ -57,6 +66,8 @@ pub struct TransactionsForLedgerCommand {
     pub ledger_id: LedgerId,
     #[structopt(long, help = "Include failed transactions")]
     pub include_failed: bool,
+    #[structopt(long, help = "Include the decoded envelope, result and meta XDR")]
+    pub decode_xdr: bool,
     #[structopt(flatten)]
     pub paging: Paging,
     #[structopt(flatten)]
@@ -92,12 +103,13 @@ where
     H: HorizonClient,
 {
     let request = api::transactions::all().with_include_failed(command.include_failed);
-    execute_and_print_stream_request(
+    execute_and_print_transformed_stream_request(
         &mut out,
         client,
         request,
         &command.paging,
         &command.streaming,
+        xdr_transform(command.decode_xdr),
     )
     .await
 }
@@ -112,7 +124,8 @@ where
     H: HorizonClient,
 {
     let request = api::transactions::single(command.transaction_id);
-    execute_and_print_request(&mut out, client, request).await
+    let transform = xdr_transform(command.decode_xdr);
+    execute_and_print_transformed_request(&mut out, client, request, transform).await
 }
 
 pub async fn run_for_account<H>(
@@ -127,12 +140,13 @@ where
     let account = PublicKey::from_account_id(&command.account_id)?;
     let request =
         api::transactions::for_account(&account).with_include_failed(command.include_failed);
-    execute_and_print_stream_request(
+    execute_and_print_transformed_stream_request(
         &mut out,
         client,
         request,
         &command.paging,
         &command.streaming,
+        xdr_transform(command.decode_xdr),
     )
     .await
 }
@@ -148,12 +162,26 @@ where
 {
     let request = api::transactions::for_ledger(command.ledger_id)
         .with_include_failed(command.include_failed);
-    execute_and_print_stream_request(
+    execute_and_print_transformed_stream_request(
         &mut out,
         client,
         request,
         &command.paging,
         &command.streaming,
+        xdr_transform(command.decode_xdr),
     )
     .await
 }
+
+/// Returns the transform applied to the transactions, decoding their XDR
+/// fields if `decode_xdr` is set.
+fn xdr_transform(decode_xdr: bool) -> fn(&mut Value) -> Result<()> {
+    if decode_xdr {
+        |value| {
+            decode_transaction_xdr(value);
+            Ok(())
+        }
+    } else {
+        |_| Ok(())
+    }
+}
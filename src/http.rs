@@ -0,0 +1,93 @@
+use anyhow::Result;
+use hyper::client::{Client, HttpConnector};
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE};
+use hyper::{Body, Method, Request};
+use hyper_tls::HttpsConnector;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// A request body and its content type.
+pub enum RequestBody {
+    Empty,
+    Form(Vec<(String, String)>),
+    Json(Value),
+}
+
+/// Sends a GET request to `url`, returning the response body.
+pub async fn get(url: &str, token: Option<&str>) -> Result<Vec<u8>> {
+    send(Method::GET, url, token, RequestBody::Empty).await
+}
+
+/// Sends a GET request to `url`, returning the parsed JSON response.
+pub async fn get_json<T>(url: &str, token: Option<&str>) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let body = get(url, token).await?;
+    parse_json(url, &body)
+}
+
+/// Sends a POST request to `url`, returning the parsed JSON response.
+pub async fn post_json<T>(url: &str, token: Option<&str>, body: RequestBody) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let body = send(Method::POST, url, token, body).await?;
+    parse_json(url, &body)
+}
+
+/// Sends a request to `url`, returning the response body if the request was
+/// successful.
+pub async fn send(
+    method: Method,
+    url: &str,
+    token: Option<&str>,
+    body: RequestBody,
+) -> Result<Vec<u8>> {
+    let client: Client<HttpsConnector<HttpConnector>> =
+        Client::builder().build(HttpsConnector::new());
+    let mut request = Request::builder().method(method).uri(url);
+    if let Some(token) = token {
+        request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+    }
+    let request = match body {
+        RequestBody::Empty => request.body(Body::empty())?,
+        RequestBody::Form(params) => {
+            let form = url::form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(params)
+                .finish();
+            request
+                .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from(form))?
+        }
+        RequestBody::Json(value) => request
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::to_vec(&value)?))?,
+    };
+    let response = client.request(request).await?;
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await?;
+    if !status.is_success() {
+        return Err(anyhow!(
+            "Request to {} failed with status {}: {}",
+            url,
+            status,
+            String::from_utf8_lossy(&body)
+        ));
+    }
+    Ok(body.to_vec())
+}
+
+/// Returns `url` with `params` added to its query string.
+pub fn with_query(url: &str, params: &[(&str, &str)]) -> Result<String> {
+    let mut url = url::Url::parse(url).map_err(|_| anyhow!("Invalid url {}", url))?;
+    url.query_pairs_mut().extend_pairs(params);
+    Ok(url.to_string())
+}
+
+fn parse_json<T>(url: &str, body: &[u8]) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    serde_json::from_slice(body).map_err(|err| anyhow!("Invalid response from {}: {}", url, err))
+}
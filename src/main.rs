@@ -14,11 +14,33 @@ use structopt::StructOpt;
 use anyhow::Result;
 
 mod account;
+mod anchor;
+mod asset;
+mod auth;
+mod claimable_balance;
 mod commands;
 mod config;
+mod data;
 mod error;
+mod fee;
 mod horizon;
+mod http;
+mod offer;
+mod pay;
+mod pool;
+mod predicate;
+mod raw_tx;
 mod render;
+mod signer;
+mod sponsor;
+mod stellar_toml;
+#[cfg(test)]
+mod test_server;
+mod trust;
+mod tx;
+mod uri;
+mod wallet;
+mod xdr;
 
 use crate::commands::OutputFormat;
 use crate::error::Error;
@@ -0,0 +1,274 @@
+use crate::asset::{parse_asset, parse_resource_asset};
+use crate::config::AppConfig;
+use crate::horizon::{execute_and_print_page_request, Paging};
+use crate::wallet::{self, TransactionOptions};
+use anyhow::Result;
+use convey::Output;
+use std::str::FromStr;
+use stellar_base::amount::{Amount, Price};
+use stellar_base::{KeyPair, Operation, PublicKey};
+use stellar_horizon::api;
+use stellar_horizon::client::HorizonClient;
+use stellar_horizon::resources::{self, OfferId};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Manage offers on the Stellar DEX")]
+pub struct OfferCommand {
+    #[structopt(short, long, global = true, help = "Which Horizon server to use")]
+    pub server: Option<String>,
+    #[structopt(subcommand)]
+    pub command: OfferInnerCommand,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum OfferInnerCommand {
+    #[structopt(about = "Creates an offer to sell an amount of an asset")]
+    Sell(SellOfferCommand),
+    #[structopt(about = "Creates an offer to buy an amount of an asset")]
+    Buy(BuyOfferCommand),
+    #[structopt(about = "Updates the amount or price of an existing offer")]
+    Update(UpdateOfferCommand),
+    #[structopt(about = "Cancels an existing offer")]
+    Cancel(CancelOfferCommand),
+    #[structopt(about = "Lists all offers of an account")]
+    List(ListOfferCommand),
+}
+
+#[derive(Debug, StructOpt)]
+pub struct SellOfferCommand {
+    #[structopt(name = "ACCOUNT_ID", help = "The seller account id, starts with G")]
+    pub account_id: String,
+    #[structopt(name = "SELLING", help = "The asset to sell. Use XLM or CODE:ISSUER.")]
+    pub selling: String,
+    #[structopt(name = "BUYING", help = "The asset to buy. Use XLM or CODE:ISSUER.")]
+    pub buying: String,
+    #[structopt(name = "AMOUNT", help = "The amount of SELLING to sell")]
+    pub amount: String,
+    #[structopt(
+        name = "PRICE",
+        help = "The price of 1 unit of SELLING in terms of BUYING, as a decimal or N/D"
+    )]
+    pub price: String,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct BuyOfferCommand {
+    #[structopt(name = "ACCOUNT_ID", help = "The buyer account id, starts with G")]
+    pub account_id: String,
+    #[structopt(name = "BUYING", help = "The asset to buy. Use XLM or CODE:ISSUER.")]
+    pub buying: String,
+    #[structopt(name = "SELLING", help = "The asset to sell. Use XLM or CODE:ISSUER.")]
+    pub selling: String,
+    #[structopt(name = "AMOUNT", help = "The amount of BUYING to buy")]
+    pub amount: String,
+    #[structopt(
+        name = "PRICE",
+        help = "The price of 1 unit of BUYING in terms of SELLING, as a decimal or N/D"
+    )]
+    pub price: String,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct UpdateOfferCommand {
+    #[structopt(
+        name = "ACCOUNT_ID",
+        help = "The offer owner account id, starts with G"
+    )]
+    pub account_id: String,
+    #[structopt(name = "OFFER_ID", help = "The offer id")]
+    pub offer_id: OfferId,
+    #[structopt(long, help = "The new amount of the selling asset")]
+    pub amount: Option<String>,
+    #[structopt(long, help = "The new price, as a decimal or N/D")]
+    pub price: Option<String>,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct CancelOfferCommand {
+    #[structopt(
+        name = "ACCOUNT_ID",
+        help = "The offer owner account id, starts with G"
+    )]
+    pub account_id: String,
+    #[structopt(name = "OFFER_ID", help = "The offer id")]
+    pub offer_id: OfferId,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ListOfferCommand {
+    #[structopt(name = "ACCOUNT_ID", help = "The Stellar account id, starts with G")]
+    pub account_id: String,
+    #[structopt(flatten)]
+    pub paging: Paging,
+}
+
+pub async fn run_command(
+    mut out: &mut Output,
+    config: &AppConfig,
+    command: OfferCommand,
+) -> Result<()> {
+    let client = wallet::horizon_client(&config, &command.server)?;
+    match command.command {
+        OfferInnerCommand::Sell(cmd) => run_sell(&mut out, &config, &client, cmd).await,
+        OfferInnerCommand::Buy(cmd) => run_buy(&mut out, &config, &client, cmd).await,
+        OfferInnerCommand::Update(cmd) => run_update(&mut out, &config, &client, cmd).await,
+        OfferInnerCommand::Cancel(cmd) => run_cancel(&mut out, &config, &client, cmd).await,
+        OfferInnerCommand::List(cmd) => run_list(&mut out, &config, &client, cmd).await,
+    }
+}
+
+pub async fn run_sell<H>(
+    mut out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: SellOfferCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let keypair = config.find_account(&command.account_id)?.keypair()?;
+    let operation = Operation::new_manage_sell_offer()
+        .with_selling_asset(parse_asset(&command.selling)?)
+        .with_buying_asset(parse_asset(&command.buying)?)
+        .with_amount(Amount::from_str(&command.amount)?)?
+        .with_price(parse_price(&command.price)?)
+        .build()?;
+    submit_offer_operation(&mut out, client, &command.transaction, keypair, operation).await
+}
+
+pub async fn run_buy<H>(
+    mut out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: BuyOfferCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let keypair = config.find_account(&command.account_id)?.keypair()?;
+    let operation = Operation::new_manage_buy_offer()
+        .with_selling_asset(parse_asset(&command.selling)?)
+        .with_buying_asset(parse_asset(&command.buying)?)
+        .with_buy_amount(Amount::from_str(&command.amount)?)?
+        .with_price(parse_price(&command.price)?)
+        .build()?;
+    submit_offer_operation(&mut out, client, &command.transaction, keypair, operation).await
+}
+
+pub async fn run_update<H>(
+    mut out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: UpdateOfferCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let keypair = config.find_account(&command.account_id)?.keypair()?;
+    let offer = load_offer(client, keypair.public_key(), command.offer_id).await?;
+    let amount = command.amount.unwrap_or_else(|| offer.amount.clone());
+    let price = match command.price {
+        None => Price::new(offer.price_ratio.numerator, offer.price_ratio.denominator),
+        Some(price) => parse_price(&price)?,
+    };
+    let operation = Operation::new_manage_sell_offer()
+        .with_selling_asset(parse_resource_asset(&offer.selling)?)
+        .with_buying_asset(parse_resource_asset(&offer.buying)?)
+        .with_amount(Amount::from_str(&amount)?)?
+        .with_price(price)
+        .with_offer_id(Some(offer.id))
+        .build()?;
+    submit_offer_operation(&mut out, client, &command.transaction, keypair, operation).await
+}
+
+pub async fn run_cancel<H>(
+    mut out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: CancelOfferCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let keypair = config.find_account(&command.account_id)?.keypair()?;
+    let offer = load_offer(client, keypair.public_key(), command.offer_id).await?;
+    let operation = Operation::new_manage_sell_offer()
+        .with_selling_asset(parse_resource_asset(&offer.selling)?)
+        .with_buying_asset(parse_resource_asset(&offer.buying)?)
+        .with_amount(Amount::from_str("0")?)?
+        .with_price(Price::new(
+            offer.price_ratio.numerator,
+            offer.price_ratio.denominator,
+        ))
+        .with_offer_id(Some(offer.id))
+        .build()?;
+    submit_offer_operation(&mut out, client, &command.transaction, keypair, operation).await
+}
+
+pub async fn run_list<H>(
+    mut out: &mut Output,
+    _config: &AppConfig,
+    client: &H,
+    command: ListOfferCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let account = PublicKey::from_account_id(&command.account_id)?;
+    let request = api::offers::for_account(&account);
+    execute_and_print_page_request(&mut out, client, request, &command.paging).await
+}
+
+pub fn parse_price(price: &str) -> Result<Price> {
+    let parts: Vec<&str> = price.split('/').collect();
+    match parts.len() {
+        1 => Ok(Price::from_str(parts[0])?),
+        2 => {
+            let numerator: i32 = parts[0].trim().parse()?;
+            let denominator: i32 = parts[1].trim().parse()?;
+            if numerator <= 0 || denominator <= 0 {
+                return Err(anyhow!("Price must be positive"));
+            }
+            Ok(Price::new(numerator, denominator))
+        }
+        _ => Err(anyhow!("Invalid price format")),
+    }
+}
+
+async fn load_offer<H>(
+    client: &H,
+    seller: &PublicKey,
+    offer_id: OfferId,
+) -> Result<resources::Offer>
+where
+    H: HorizonClient,
+{
+    let (_, offer) = client.request(api::offers::single(offer_id)).await?;
+    if offer.seller != seller.account_id() {
+        return Err(anyhow!("Offer {} is not owned by {}", offer_id, seller));
+    }
+    Ok(offer)
+}
+
+async fn submit_offer_operation<H>(
+    mut out: &mut Output,
+    client: &H,
+    options: &TransactionOptions,
+    keypair: KeyPair,
+    operation: Operation,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let tx = wallet::build_transaction(client, keypair.public_key(), vec![operation]).await?;
+    wallet::sign_and_submit(&mut out, client, options, tx, &[keypair]).await
+}
@@ -0,0 +1,396 @@
+use crate::asset::{find_balance, parse_asset};
+use crate::config::AppConfig;
+use crate::error::Error;
+use crate::fee;
+use crate::render::ResponseRender;
+use crate::wallet::{self, TransactionOptions, TransactionXdr};
+use anyhow::Result;
+use convey::components::{newline, text};
+use convey::{Output, Render};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+use stellar_base::amount::Amount;
+use stellar_base::xdr::{XDRSerialize, MAX_OPS_PER_TX};
+use stellar_base::{Asset, Memo, Operation};
+use stellar_horizon::api;
+use stellar_horizon::client::HorizonClient;
+use stellar_horizon::error::Error as HorizonError;
+use stellar_horizon::horizon_error::HorizonError as HorizonRequestError;
+use stellar_horizon::resources;
+use structopt::StructOpt;
+
+const RESULTS_HEADER: &str = "row,destination,amount,asset,memo,status,tx_hash";
+
+#[derive(Debug, StructOpt)]
+pub struct BatchCommand {
+    #[structopt(
+        name = "FILE",
+        help = "The CSV file with destination, amount and optional asset, memo and memo_type columns"
+    )]
+    pub file: String,
+    #[structopt(long, help = "The sender account id, starts with G, or its name")]
+    pub from: String,
+    #[structopt(
+        long,
+        help = "Resume from this row of FILE, as reported by a failed batch"
+    )]
+    pub start_row: Option<usize>,
+    #[structopt(
+        long,
+        help = "The results CSV file, defaults to FILE with a .results.csv extension"
+    )]
+    pub results: Option<String>,
+    #[structopt(
+        long,
+        help = "Send even if the destination requires a memo and there is none"
+    )]
+    pub skip_memo_check: bool,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+/// A row of the payments file, the row number is its line in the file.
+#[derive(Debug, Clone)]
+struct PaymentRow {
+    row: usize,
+    destination: String,
+    amount: String,
+    asset: String,
+    memo: String,
+    memo_type: String,
+}
+
+#[derive(Debug)]
+struct Payment {
+    row: PaymentRow,
+    operation: Operation,
+    memo: Memo,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchTransaction {
+    pub first_row: usize,
+    pub last_row: usize,
+    pub payments: usize,
+    pub hash: String,
+    pub status: String,
+}
+
+pub async fn run_batch<H>(
+    out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: BatchCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let keypair = config.find_account(&command.from)?.keypair()?;
+    let source = keypair.public_key();
+    let start_row = command.start_row.unwrap_or(0);
+    let rows = read_rows(&command.file)?
+        .into_iter()
+        .filter(|row| row.row >= start_row)
+        .collect::<Vec<_>>();
+    if rows.is_empty() {
+        return Err(anyhow!("No payments to send"));
+    }
+    let payments = validate_rows(config, client, rows, command.skip_memo_check).await?;
+
+    let network = wallet::network(client).await?;
+    let account = wallet::load_account(client, source).await?;
+    let sequences = wallet::next_sequence(&account)?..;
+    let mut results = if command.transaction.no_submit {
+        None
+    } else {
+        let path = command
+            .results
+            .clone()
+            .unwrap_or_else(|| results_path(&command.file));
+        Some(open_results(&path, command.start_row.is_some())?)
+    };
+    for (batch, sequence) in batches(&payments).into_iter().zip(sequences) {
+        let operations = batch.iter().map(|p| p.operation.clone()).collect();
+        let mut tx = wallet::build_transaction_with_sequence(source, sequence, operations)?;
+        *tx.memo_mut() = batch[0].memo.clone();
+        fee::apply_fee_strategy(out, client, &command.transaction, &mut tx).await?;
+        tx.sign(&keypair, &network)?;
+        let envelope = tx.into_envelope();
+        let results = match results.as_mut() {
+            None => {
+                let xdr = envelope.xdr_base64()?;
+                out.print(TransactionXdr { xdr }).map_err(Error::Convey)?;
+                continue;
+            }
+            Some(results) => results,
+        };
+        let hash = hex::encode(envelope.hash(&network)?);
+        let first_row = batch[0].row.row;
+        let request = api::transactions::submit(&envelope)?;
+        let (status, error) = match client.request(request).await {
+            Ok(_) => ("success", None),
+            Err(HorizonError::HorizonRequestError(err)) => {
+                out.print(ResponseRender(err.clone()))
+                    .map_err(Error::Convey)?;
+                match err {
+                    HorizonRequestError::BadRequest(_)
+                    | HorizonRequestError::TransactionFailed(_)
+                    | HorizonRequestError::TransactionMalformed(_) => {
+                        ("failed", Some(HorizonError::HorizonRequestError(err)))
+                    }
+                    _ => ("unknown", Some(HorizonError::HorizonRequestError(err))),
+                }
+            }
+            Err(err) => ("unknown", Some(err)),
+        };
+        for payment in &batch {
+            write_result(results, &payment.row, status, &hash)?;
+        }
+        let summary = BatchTransaction {
+            first_row,
+            last_row: batch[batch.len() - 1].row.row,
+            payments: batch.len(),
+            hash: hash.clone(),
+            status: status.to_string(),
+        };
+        out.print(summary).map_err(Error::Convey)?;
+        if let Some(err) = error {
+            let message = if status == "failed" {
+                format!(
+                    "Payments from row {} failed, resume with --start-row {}",
+                    first_row, first_row
+                )
+            } else {
+                format!(
+                    "The result of transaction {} is unknown, check it before resuming with --start-row {}",
+                    hash, first_row
+                )
+            };
+            return Err(anyhow::Error::from(err).context(message));
+        }
+    }
+    Ok(())
+}
+
+fn read_rows(path: &str) -> Result<Vec<PaymentRow>> {
+    let content =
+        std::fs::read_to_string(path).map_err(|err| anyhow!("Cannot read {}: {}", path, err))?;
+    let mut lines = content.lines().enumerate();
+    let header = match lines.next() {
+        None => return Err(anyhow!("The payments file is empty")),
+        Some((_, header)) => parse_csv_line(header),
+    };
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|c| c.trim().eq_ignore_ascii_case(name))
+    };
+    let destination = column("destination").ok_or_else(|| anyhow!("Missing destination column"))?;
+    let amount = column("amount").ok_or_else(|| anyhow!("Missing amount column"))?;
+    let asset = column("asset");
+    let memo = column("memo");
+    let memo_type = column("memo_type");
+    let mut rows = Vec::new();
+    for (index, line) in lines {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = parse_csv_line(line);
+        let field = |column: Option<usize>| {
+            column
+                .and_then(|c| fields.get(c))
+                .map_or_else(String::new, |f| f.trim().to_string())
+        };
+        rows.push(PaymentRow {
+            row: index + 1,
+            destination: field(Some(destination)),
+            amount: field(Some(amount)),
+            asset: field(asset),
+            memo: field(memo),
+            memo_type: field(memo_type),
+        });
+    }
+    Ok(rows)
+}
+
+/// Checks every row before sending anything, so that a batch does not stop
+/// halfway through because of a typo.
+async fn validate_rows<H>(
+    config: &AppConfig,
+    client: &H,
+    rows: Vec<PaymentRow>,
+    skip_memo_check: bool,
+) -> Result<Vec<Payment>>
+where
+    H: HorizonClient,
+{
+    let mut accounts = HashMap::new();
+    let mut payments = Vec::new();
+    let mut errors = Vec::new();
+    for row in rows {
+        match validate_row(config, client, &mut accounts, &row, skip_memo_check).await {
+            Ok((operation, memo)) => payments.push(Payment {
+                row,
+                operation,
+                memo,
+            }),
+            Err(err) => errors.push(format!("row {}: {}", row.row, err)),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(anyhow!("Invalid payments\n{}", errors.join("\n")));
+    }
+    Ok(payments)
+}
+
+async fn validate_row<H>(
+    config: &AppConfig,
+    client: &H,
+    accounts: &mut HashMap<String, Option<resources::Account>>,
+    row: &PaymentRow,
+    skip_memo_check: bool,
+) -> Result<(Operation, Memo)>
+where
+    H: HorizonClient,
+{
+    let destination = config.resolve_public_key(&row.destination)?;
+    let amount =
+        Amount::from_str(&row.amount).map_err(|_| anyhow!("Invalid amount {}", row.amount))?;
+    if amount.to_stroops()?.to_i64() <= 0 {
+        return Err(anyhow!("Invalid amount {}", row.amount));
+    }
+    let asset = if row.asset.is_empty() {
+        Asset::new_native()
+    } else {
+        parse_asset(&row.asset)?
+    };
+    let memo = match row.memo_type.to_ascii_lowercase().as_str() {
+        _ if row.memo.is_empty() => Memo::new_none(),
+        "" | "text" => Memo::new_text(row.memo.as_str())?,
+        "id" => Memo::new_id(
+            row.memo
+                .parse()
+                .map_err(|_| anyhow!("Invalid id memo {}", row.memo))?,
+        ),
+        memo_type => return Err(anyhow!("Invalid memo type {}", memo_type)),
+    };
+
+    let key = destination.account_id();
+    if !accounts.contains_key(&key) {
+        let account = wallet::load_account_if_exists(client, &destination).await?;
+        accounts.insert(key.clone(), account);
+    }
+    let account = accounts[&key]
+        .as_ref()
+        .ok_or_else(|| anyhow!("Destination {} does not exist", key))?;
+    if asset.as_credit().is_some() && find_balance(account, &asset).is_none() {
+        return Err(anyhow!(
+            "Destination {} has no trustline to {}",
+            key,
+            row.asset
+        ));
+    }
+    if wallet::requires_memo(account) && row.memo.is_empty() && !skip_memo_check {
+        return Err(anyhow!("Destination {} requires a memo", key));
+    }
+    let operation = Operation::new_payment()
+        .with_destination(destination)
+        .with_amount(amount)?
+        .with_asset(asset)
+        .build()?;
+    Ok((operation, memo))
+}
+
+/// Groups consecutive payments with the same memo, since a transaction has
+/// a single memo.
+fn batches(payments: &[Payment]) -> Vec<Vec<&Payment>> {
+    let mut batches: Vec<Vec<&Payment>> = Vec::new();
+    for payment in payments {
+        match batches.last_mut() {
+            Some(batch)
+                if batch.len() < MAX_OPS_PER_TX as usize && batch[0].memo == payment.memo =>
+            {
+                batch.push(payment)
+            }
+            _ => batches.push(vec![payment]),
+        }
+    }
+    batches
+}
+
+fn results_path(file: &str) -> String {
+    let path = Path::new(file).with_extension("results.csv");
+    path.to_string_lossy().to_string()
+}
+
+/// Opens the results file, appending to it when resuming a batch.
+fn open_results(path: &str, resume: bool) -> Result<File> {
+    if resume && Path::new(path).exists() {
+        return Ok(OpenOptions::new().append(true).open(path)?);
+    }
+    let mut file = File::create(path)?;
+    writeln!(file, "{}", RESULTS_HEADER)?;
+    Ok(file)
+}
+
+fn write_result(file: &mut File, row: &PaymentRow, status: &str, hash: &str) -> Result<()> {
+    let fields = [
+        row.row.to_string(),
+        row.destination.clone(),
+        row.amount.clone(),
+        row.asset.clone(),
+        row.memo.clone(),
+        status.to_string(),
+        hash.to_string(),
+    ];
+    let line = fields
+        .iter()
+        .map(|f| csv_field(f))
+        .collect::<Vec<_>>()
+        .join(",");
+    writeln!(file, "{}", line)?;
+    file.flush()?;
+    Ok(())
+}
+
+fn parse_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains(&[',', '"', '\n'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl Render for BatchTransaction {
+    render_for_humans!(self -> [
+        text(format!("rows {}-{}", self.first_row, self.last_row)), text("\t"),
+        text(self.payments.to_string()), text("\t"),
+        text(&self.hash), text("\t"),
+        text(&self.status), newline(),
+    ]);
+
+    render_json!();
+}
@@ -0,0 +1,252 @@
+use crate::asset::{format_asset, format_resource_asset, parse_asset, parse_resource_asset};
+use crate::config::AppConfig;
+use crate::wallet::{self, MemoOptions, TransactionOptions};
+use anyhow::Result;
+use convey::Output;
+use std::convert::TryFrom;
+use std::str::FromStr;
+use stellar_base::amount::{Amount, Stroops};
+use stellar_base::{Asset, Operation, PublicKey};
+use stellar_horizon::api;
+use stellar_horizon::client::HorizonClient;
+use stellar_horizon::resources;
+use structopt::clap::AppSettings;
+use structopt::StructOpt;
+
+mod batch;
+
+const ONE_HUNDRED_PERCENT: i128 = 100 * 10_000_000;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    about = "Sends a payment",
+    setting = AppSettings::ArgsNegateSubcommands
+)]
+pub struct PayCommand {
+    #[structopt(short, long, global = true, help = "Which Horizon server to use")]
+    pub server: Option<String>,
+    #[structopt(name = "FROM", help = "The sender account id, starts with G")]
+    pub from: Option<String>,
+    #[structopt(name = "TO", help = "The destination account id, starts with G")]
+    pub to: Option<String>,
+    #[structopt(
+        name = "AMOUNT",
+        help = "The amount of ASSET sent or, with --path-receive, received"
+    )]
+    pub amount: Option<String>,
+    #[structopt(
+        long,
+        default_value = "XLM",
+        help = "The asset paid. Use XLM for the native asset, and CODE:ISSUER for all other assets."
+    )]
+    pub asset: String,
+    #[structopt(
+        long,
+        name = "DESTINATION_ASSET",
+        conflicts_with = "SOURCE_ASSET",
+        help = "Send exactly AMOUNT of --asset, the destination receives DESTINATION_ASSET"
+    )]
+    pub path_send: Option<String>,
+    #[structopt(
+        long,
+        name = "SOURCE_ASSET",
+        help = "The destination receives exactly AMOUNT of --asset, paid with SOURCE_ASSET"
+    )]
+    pub path_receive: Option<String>,
+    #[structopt(
+        long,
+        default_value = "1",
+        help = "The slippage tolerance in percent applied to path payments"
+    )]
+    pub slippage: String,
+    #[structopt(
+        long,
+        help = "Use the N-th best path instead of the best one, starting from 0"
+    )]
+    pub path_index: Option<usize>,
+    #[structopt(flatten)]
+    pub memo: MemoOptions,
+    #[structopt(
+        long,
+        help = "Send even if the destination requires a memo and there is none"
+    )]
+    pub skip_memo_check: bool,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+    #[structopt(subcommand)]
+    pub command: Option<PayInnerCommand>,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum PayInnerCommand {
+    #[structopt(about = "Sends the payments listed in a CSV file")]
+    Batch(batch::BatchCommand),
+}
+
+pub async fn run_command(
+    mut out: &mut Output,
+    config: &AppConfig,
+    command: PayCommand,
+) -> Result<()> {
+    let client = wallet::horizon_client(&config, &command.server)?;
+    if let Some(PayInnerCommand::Batch(cmd)) = command.command {
+        return batch::run_batch(&mut out, &config, &client, cmd).await;
+    }
+    let keypair = config.find_account(required(&command.from)?)?.keypair()?;
+    let destination = PublicKey::from_account_id(required(&command.to)?)?;
+    let asset = parse_asset(&command.asset)?;
+    let operation = match (&command.path_send, &command.path_receive) {
+        (Some(destination_asset), _) => {
+            let destination_asset = parse_asset(destination_asset)?;
+            path_send_operation(&client, &command, destination, asset, destination_asset).await?
+        }
+        (None, Some(source_asset)) => {
+            let source_asset = parse_asset(source_asset)?;
+            path_receive_operation(
+                &client,
+                &command,
+                keypair.public_key(),
+                destination,
+                source_asset,
+                asset,
+            )
+            .await?
+        }
+        (None, None) => Operation::new_payment()
+            .with_destination(destination)
+            .with_amount(Amount::from_str(required(&command.amount)?)?)?
+            .with_asset(asset)
+            .build()?,
+    };
+    let mut tx = wallet::build_transaction(&client, keypair.public_key(), vec![operation]).await?;
+    *tx.memo_mut() = command.memo.to_memo()?;
+    if !command.skip_memo_check {
+        wallet::check_memo_required(&client, &tx).await?;
+    }
+    wallet::sign_and_submit(&mut out, &client, &command.transaction, tx, &[keypair]).await
+}
+
+async fn path_send_operation<H>(
+    client: &H,
+    command: &PayCommand,
+    destination: PublicKey,
+    send_asset: Asset,
+    destination_asset: Asset,
+) -> Result<Operation>
+where
+    H: HorizonClient,
+{
+    let send_amount = Amount::from_str(required(&command.amount)?)?;
+    let request =
+        api::aggregations::paths_strict_send(send_asset.clone(), vec![], send_amount.clone())?
+            .with_destination_account(&destination);
+    let (_, response) = client.request(request).await?;
+    let mut paths = filter_paths(response.records, &destination_asset, |p| {
+        &p.destination_asset
+    });
+    // Best path first: the one delivering the most to the destination.
+    paths.sort_by_key(|p| std::cmp::Reverse(stroops(&p.destination_amount).unwrap_or(0)));
+    let path = select_path(paths, command.path_index)?;
+    let slippage = parse_slippage(&command.slippage)?;
+    let destination_min = apply_slippage(&path.destination_amount, slippage, false)?;
+    let mut builder = Operation::new_path_payment_strict_send()
+        .with_destination(destination)
+        .with_send_asset(send_asset)
+        .with_send_amount(send_amount)?
+        .with_destination_asset(destination_asset)
+        .with_destination_min(destination_min)?;
+    for asset in &path.path {
+        builder = builder.add_asset(parse_resource_asset(asset)?);
+    }
+    Ok(builder.build()?)
+}
+
+async fn path_receive_operation<H>(
+    client: &H,
+    command: &PayCommand,
+    source: &PublicKey,
+    destination: PublicKey,
+    send_asset: Asset,
+    destination_asset: Asset,
+) -> Result<Operation>
+where
+    H: HorizonClient,
+{
+    let destination_amount = Amount::from_str(required(&command.amount)?)?;
+    let request = api::aggregations::paths_strict_receive(
+        vec![],
+        destination_asset.clone(),
+        destination_amount.clone(),
+    )?
+    .with_source_account(source);
+    let (_, response) = client.request(request).await?;
+    let mut paths = filter_paths(response.records, &send_asset, |p| &p.source_asset);
+    // Best path first: the one costing the least to the source.
+    paths.sort_by_key(|p| stroops(&p.source_amount).unwrap_or(i64::MAX));
+    let path = select_path(paths, command.path_index)?;
+    let slippage = parse_slippage(&command.slippage)?;
+    let send_max = apply_slippage(&path.source_amount, slippage, true)?;
+    let mut builder = Operation::new_path_payment_strict_receive()
+        .with_destination(destination)
+        .with_send_asset(send_asset)
+        .with_send_max(send_max)?
+        .with_destination_asset(destination_asset)
+        .with_destination_amount(destination_amount)?;
+    for asset in &path.path {
+        builder = builder.add_asset(parse_resource_asset(asset)?);
+    }
+    Ok(builder.build()?)
+}
+
+pub fn filter_paths<F>(
+    paths: Vec<resources::Path>,
+    asset: &Asset,
+    path_asset: F,
+) -> Vec<resources::Path>
+where
+    F: Fn(&resources::Path) -> &resources::Asset,
+{
+    let asset = format_asset(asset);
+    paths
+        .into_iter()
+        .filter(|p| format_resource_asset(path_asset(p)) == asset)
+        .collect()
+}
+
+fn select_path(paths: Vec<resources::Path>, index: Option<usize>) -> Result<resources::Path> {
+    let index = index.unwrap_or(0);
+    paths
+        .into_iter()
+        .nth(index)
+        .ok_or_else(|| anyhow!("No payment path found"))
+}
+
+/// Returns an argument that is required unless a subcommand is used.
+fn required(value: &Option<String>) -> Result<&str> {
+    value
+        .as_deref()
+        .ok_or_else(|| anyhow!("Missing FROM, TO or AMOUNT"))
+}
+
+pub fn stroops(amount: &str) -> Result<i64> {
+    Ok(Amount::from_str(amount)?.to_stroops()?.to_i64())
+}
+
+pub fn parse_slippage(slippage: &str) -> Result<i128> {
+    let slippage = stroops(slippage)? as i128;
+    if !(0..ONE_HUNDRED_PERCENT).contains(&slippage) {
+        return Err(anyhow!("Slippage must be between 0 and 100 percent"));
+    }
+    Ok(slippage)
+}
+
+pub fn apply_slippage(amount: &str, slippage: i128, increase: bool) -> Result<Stroops> {
+    let amount = stroops(amount)? as i128;
+    let result = if increase {
+        let scaled = amount * (ONE_HUNDRED_PERCENT + slippage);
+        (scaled + ONE_HUNDRED_PERCENT - 1) / ONE_HUNDRED_PERCENT
+    } else {
+        amount * (ONE_HUNDRED_PERCENT - slippage) / ONE_HUNDRED_PERCENT
+    };
+    Ok(Stroops::new(i64::try_from(result)?))
+}
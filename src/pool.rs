@@ -0,0 +1,858 @@
+use crate::asset::{format_asset, parse_asset};
+use crate::config::AppConfig;
+use crate::error::Error;
+use crate::horizon::{add_paging_options, Paging};
+use crate::pay::{apply_slippage, parse_slippage, stroops};
+use crate::raw_tx;
+use crate::wallet::{self, TransactionOptions};
+use anyhow::Result;
+use convey::components::{newline, text};
+use convey::{Output, Render};
+use std::convert::{TryFrom, TryInto};
+use std::str::FromStr;
+use stellar_base::amount::{Amount, Price, Stroops};
+use stellar_base::crypto;
+use stellar_base::xdr::XDRSerialize;
+use stellar_base::{Asset, PublicKey};
+use stellar_horizon::client::HorizonClient;
+use stellar_horizon::error::Error as HorizonError;
+use stellar_horizon::horizon_error::HorizonError as HorizonRequestError;
+use stellar_horizon::page::Page;
+use stellar_horizon::request::{Order, PageRequest, Request};
+use stellar_xdr::curr as xdr;
+use structopt::StructOpt;
+use url::Url;
+
+/// The constant product pool type, the only one supported by the network.
+const LIQUIDITY_POOL_CONSTANT_PRODUCT: i32 = 0;
+/// The fee of constant product pools, in basis points.
+const LIQUIDITY_POOL_FEE: i32 = 30;
+const ONE: i128 = 10_000_000;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Query and use liquidity pools")]
+pub struct PoolCommand {
+    #[structopt(short, long, global = true, help = "Which Horizon server to use")]
+    pub server: Option<String>,
+    #[structopt(subcommand)]
+    pub command: PoolInnerCommand,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum PoolInnerCommand {
+    #[structopt(about = "Lists liquidity pools")]
+    List(ListPoolCommand),
+    #[structopt(about = "Shows a liquidity pool")]
+    Show(ShowPoolCommand),
+    #[structopt(about = "Lists the pool shares held by an account")]
+    ForAccount(ForAccountPoolCommand),
+    #[structopt(about = "Computes the id of the liquidity pool of two assets")]
+    Id(IdPoolCommand),
+    #[structopt(about = "Deposits assets into a liquidity pool")]
+    Deposit(DepositPoolCommand),
+    #[structopt(about = "Withdraws assets from a liquidity pool")]
+    Withdraw(WithdrawPoolCommand),
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ListPoolCommand {
+    #[structopt(
+        long = "reserve",
+        number_of_values = 1,
+        help = "Only list pools holding this asset, can be repeated. Use XLM or CODE:ISSUER."
+    )]
+    pub reserves: Vec<String>,
+    #[structopt(flatten)]
+    pub paging: Paging,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ShowPoolCommand {
+    #[structopt(name = "POOL_ID", help = "The liquidity pool id")]
+    pub pool_id: String,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ForAccountPoolCommand {
+    #[structopt(
+        name = "ACCOUNT_ID",
+        help = "The account id, starts with G, or its name"
+    )]
+    pub account_id: String,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct IdPoolCommand {
+    #[structopt(name = "ASSET_A", help = "One of the assets. Use XLM or CODE:ISSUER.")]
+    pub asset_a: String,
+    #[structopt(name = "ASSET_B", help = "The other asset. Use XLM or CODE:ISSUER.")]
+    pub asset_b: String,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct DepositPoolCommand {
+    #[structopt(name = "ACCOUNT_ID", help = "The depositing account id, starts with G")]
+    pub account_id: String,
+    #[structopt(name = "ASSET_A", help = "One of the assets. Use XLM or CODE:ISSUER.")]
+    pub asset_a: String,
+    #[structopt(name = "AMOUNT_A", help = "The maximum amount of ASSET_A deposited")]
+    pub amount_a: String,
+    #[structopt(name = "ASSET_B", help = "The other asset. Use XLM or CODE:ISSUER.")]
+    pub asset_b: String,
+    #[structopt(name = "AMOUNT_B", help = "The maximum amount of ASSET_B deposited")]
+    pub amount_b: String,
+    #[structopt(
+        long,
+        default_value = "1",
+        help = "The slippage tolerance in percent applied to the pool price"
+    )]
+    pub slippage: String,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct WithdrawPoolCommand {
+    #[structopt(
+        name = "ACCOUNT_ID",
+        help = "The withdrawing account id, starts with G"
+    )]
+    pub account_id: String,
+    #[structopt(name = "ASSET_A", help = "One of the assets. Use XLM or CODE:ISSUER.")]
+    pub asset_a: String,
+    #[structopt(name = "ASSET_B", help = "The other asset. Use XLM or CODE:ISSUER.")]
+    pub asset_b: String,
+    #[structopt(name = "SHARES", help = "The amount of pool shares redeemed")]
+    pub shares: String,
+    #[structopt(
+        long,
+        default_value = "1",
+        help = "The slippage tolerance in percent applied to the amounts received"
+    )]
+    pub slippage: String,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LiquidityPool {
+    pub id: String,
+    pub fee_bp: u32,
+    #[serde(rename = "type")]
+    pub pool_type: String,
+    pub total_trustlines: String,
+    pub total_shares: String,
+    pub reserves: Vec<LiquidityPoolReserve>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LiquidityPoolReserve {
+    pub asset: String,
+    pub amount: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LiquidityPoolList {
+    pub pools: Vec<LiquidityPool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PoolShares {
+    pub pool_id: String,
+    pub shares: String,
+    /// The part of the pool reserves the shares can be redeemed for.
+    pub reserves: Vec<LiquidityPoolReserve>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PoolSharesList {
+    pub account_id: String,
+    pub pools: Vec<PoolShares>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PoolId {
+    pub id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DepositPlan {
+    pub pool_id: String,
+    pub account_id: String,
+    pub max_amounts: Vec<LiquidityPoolReserve>,
+    pub min_price: String,
+    pub max_price: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WithdrawPlan {
+    pub pool_id: String,
+    pub account_id: String,
+    pub shares: String,
+    pub min_amounts: Vec<LiquidityPoolReserve>,
+}
+
+/// Horizon request for the liquidity pools, filtered by reserves.
+#[derive(Default)]
+struct LiquidityPoolsRequest {
+    reserves: Vec<String>,
+    cursor: Option<String>,
+    limit: Option<u64>,
+    order: Option<Order>,
+}
+
+/// Horizon request for a single liquidity pool.
+struct LiquidityPoolRequest {
+    pool_id: String,
+}
+
+/// Horizon request for the balances of an account, including the pool
+/// shares that the account resource does not have.
+struct AccountBalancesRequest {
+    account_id: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct AccountBalances {
+    balances: Vec<AccountBalance>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct AccountBalance {
+    balance: String,
+    asset_type: String,
+    liquidity_pool_id: Option<String>,
+}
+
+impl Request for LiquidityPoolsRequest {
+    type Response = Page<LiquidityPool>;
+
+    fn uri(&self, host: &Url) -> stellar_horizon::error::Result<Url> {
+        let mut url = host.join("/liquidity_pools")?;
+        {
+            let mut query = url.query_pairs_mut();
+            if !self.reserves.is_empty() {
+                query.append_pair("reserves", &self.reserves.join(","));
+            }
+            if let Some(cursor) = &self.cursor {
+                query.append_pair("cursor", cursor);
+            }
+            if let Some(limit) = self.limit {
+                query.append_pair("limit", &limit.to_string());
+            }
+            if let Some(order) = &self.order {
+                query.append_pair("order", &order.to_query_value());
+            }
+        }
+        Ok(url)
+    }
+}
+
+impl PageRequest for LiquidityPoolsRequest {
+    fn with_cursor(mut self, cursor: &str) -> Self {
+        self.cursor = Some(cursor.to_string());
+        self
+    }
+
+    fn cursor(&self) -> &Option<String> {
+        &self.cursor
+    }
+
+    fn with_limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    fn limit(&self) -> &Option<u64> {
+        &self.limit
+    }
+
+    fn with_order(mut self, order: &Order) -> Self {
+        self.order = Some(*order);
+        self
+    }
+
+    fn order(&self) -> &Option<Order> {
+        &self.order
+    }
+}
+
+impl Request for LiquidityPoolRequest {
+    type Response = LiquidityPool;
+
+    fn uri(&self, host: &Url) -> stellar_horizon::error::Result<Url> {
+        Ok(host.join(&format!("/liquidity_pools/{}", self.pool_id))?)
+    }
+}
+
+impl Request for AccountBalancesRequest {
+    type Response = AccountBalances;
+
+    fn uri(&self, host: &Url) -> stellar_horizon::error::Result<Url> {
+        Ok(host.join(&format!("/accounts/{}", self.account_id))?)
+    }
+}
+
+pub async fn run_command(out: &mut Output, config: &AppConfig, command: PoolCommand) -> Result<()> {
+    let client = wallet::horizon_client(&config, &command.server)?;
+    match command.command {
+        PoolInnerCommand::List(cmd) => run_list(out, &client, cmd).await,
+        PoolInnerCommand::Show(cmd) => run_show(out, &client, cmd).await,
+        PoolInnerCommand::ForAccount(cmd) => run_for_account(out, config, &client, cmd).await,
+        PoolInnerCommand::Id(cmd) => run_id(out, cmd),
+        PoolInnerCommand::Deposit(cmd) => run_deposit(out, config, &client, cmd).await,
+        PoolInnerCommand::Withdraw(cmd) => run_withdraw(out, config, &client, cmd).await,
+    }
+}
+
+async fn run_list<H>(out: &mut Output, client: &H, command: ListPoolCommand) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let mut reserves = Vec::new();
+    for reserve in &command.reserves {
+        reserves.push(canonical_asset(&parse_asset(reserve)?));
+    }
+    let request = LiquidityPoolsRequest {
+        reserves,
+        ..Default::default()
+    };
+    let request = add_paging_options(request, &command.paging);
+    let (_, response) = client.request(request).await?;
+    out.print(LiquidityPoolList {
+        pools: response.records,
+    })
+    .map_err(Error::Convey)?;
+    Ok(())
+}
+
+async fn run_show<H>(out: &mut Output, client: &H, command: ShowPoolCommand) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let pool_id = parse_pool_id(&command.pool_id)?;
+    let pool = load_pool(client, &pool_id)
+        .await?
+        .ok_or_else(|| anyhow!("Liquidity pool {} not found", pool_id))?;
+    out.print(pool).map_err(Error::Convey)?;
+    Ok(())
+}
+
+async fn run_for_account<H>(
+    out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: ForAccountPoolCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let account = config.resolve_public_key(&command.account_id)?;
+    let mut pools = Vec::new();
+    for (pool_id, shares) in account_shares(client, &account).await? {
+        let pool = load_pool(client, &pool_id)
+            .await?
+            .ok_or_else(|| anyhow!("Liquidity pool {} not found", pool_id))?;
+        let reserves = pool
+            .reserves
+            .iter()
+            .map(|reserve| {
+                Ok(LiquidityPoolReserve {
+                    asset: reserve.asset.clone(),
+                    amount: amount_string(share_of(&reserve.amount, &shares, &pool)?)?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        pools.push(PoolShares {
+            pool_id,
+            shares,
+            reserves,
+        });
+    }
+    out.print(PoolSharesList {
+        account_id: account.account_id(),
+        pools,
+    })
+    .map_err(Error::Convey)?;
+    Ok(())
+}
+
+fn run_id(out: &mut Output, command: IdPoolCommand) -> Result<()> {
+    let asset_a = parse_asset(&command.asset_a)?;
+    let asset_b = parse_asset(&command.asset_b)?;
+    let id = pool_id(&asset_a, &asset_b)?;
+    out.print(PoolId { id }).map_err(Error::Convey)?;
+    Ok(())
+}
+
+async fn run_deposit<H>(
+    out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: DepositPoolCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let keypair = config.find_account(&command.account_id)?.keypair()?;
+    let account = keypair.public_key().clone();
+    let slippage = parse_slippage(&command.slippage)?;
+    let asset_a = parse_asset(&command.asset_a)?;
+    let asset_b = parse_asset(&command.asset_b)?;
+    let mut deposits = [
+        (asset_a, stroops(&command.amount_a)?),
+        (asset_b, stroops(&command.amount_b)?),
+    ];
+    if deposits.iter().any(|(_, amount)| *amount <= 0) {
+        return Err(anyhow!("Deposit amounts must be positive"));
+    }
+    let pool_id = pool_id(&deposits[0].0, &deposits[1].0)?;
+    if !is_sorted(&deposits[0].0, &deposits[1].0)? {
+        deposits.swap(0, 1);
+    }
+
+    // The first deposit into a pool sets its price.
+    let price = match load_pool(client, &pool_id).await? {
+        Some(pool) if stroops(&pool.total_shares)? > 0 => {
+            let reserve_a = stroops(&reserve_amount(&pool, &deposits[0].0)?)?;
+            let reserve_b = stroops(&reserve_amount(&pool, &deposits[1].0)?)?;
+            ratio(reserve_a, reserve_b)?
+        }
+        _ => ratio(deposits[0].1, deposits[1].1)?,
+    };
+    let price = amount_string(price as i128)?;
+    let min_price = Amount::from_stroops(&apply_slippage(&price, slippage, false)?)?;
+    let max_price = Amount::from_stroops(&apply_slippage(&price, slippage, true)?)?;
+    let deposit = xdr::OperationBody::LiquidityPoolDeposit(xdr::LiquidityPoolDepositOp {
+        liquidity_pool_id: network_pool_id(&pool_id)?,
+        max_amount_a: deposits[0].1,
+        max_amount_b: deposits[1].1,
+        min_price: network_price(&min_price)?,
+        max_price: network_price(&max_price)?,
+    });
+    let max_amounts = deposits
+        .iter()
+        .map(|(asset, amount)| {
+            Ok(LiquidityPoolReserve {
+                asset: canonical_asset(asset),
+                amount: amount_string(*amount as i128)?,
+            })
+        })
+        .collect::<Result<_>>()?;
+    let has_trustline = account_shares(client, &account)
+        .await?
+        .iter()
+        .any(|(id, _)| *id == pool_id);
+    out.print(DepositPlan {
+        pool_id,
+        account_id: account.account_id(),
+        max_amounts,
+        min_price: min_price.to_string(),
+        max_price: max_price.to_string(),
+    })
+    .map_err(Error::Convey)?;
+
+    // The pool shares are held in a trustline, which also creates the pool.
+    let mut operations = Vec::new();
+    if !has_trustline {
+        let parameters = xdr::LiquidityPoolConstantProductParameters {
+            asset_a: raw_tx::asset(&deposits[0].0)?,
+            asset_b: raw_tx::asset(&deposits[1].0)?,
+            fee: LIQUIDITY_POOL_FEE,
+        };
+        operations.push(xdr::OperationBody::ChangeTrust(xdr::ChangeTrustOp {
+            line: xdr::ChangeTrustAsset::PoolShare(
+                xdr::LiquidityPoolParameters::LiquidityPoolConstantProduct(parameters),
+            ),
+            limit: i64::MAX,
+        }));
+    }
+    operations.push(deposit);
+    let tx = raw_tx::build_transaction(client, &account, operations).await?;
+    raw_tx::sign_and_submit(out, client, &command.transaction, tx, &[keypair]).await
+}
+
+async fn run_withdraw<H>(
+    out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: WithdrawPoolCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let keypair = config.find_account(&command.account_id)?.keypair()?;
+    let account = keypair.public_key().clone();
+    let slippage = parse_slippage(&command.slippage)?;
+    let mut assets = [
+        parse_asset(&command.asset_a)?,
+        parse_asset(&command.asset_b)?,
+    ];
+    let pool_id = pool_id(&assets[0], &assets[1])?;
+    if !is_sorted(&assets[0], &assets[1])? {
+        assets.swap(0, 1);
+    }
+    let shares = stroops(&command.shares)?;
+    if shares <= 0 {
+        return Err(anyhow!("The amount of shares must be positive"));
+    }
+    let held = account_shares(client, &account)
+        .await?
+        .into_iter()
+        .find(|(id, _)| *id == pool_id)
+        .map(|(_, shares)| shares)
+        .ok_or_else(|| {
+            anyhow!(
+                "Account {} has no shares of pool {}",
+                command.account_id,
+                pool_id
+            )
+        })?;
+    if stroops(&held)? < shares {
+        return Err(anyhow!(
+            "Account {} has only {} shares of pool {}",
+            command.account_id,
+            held,
+            pool_id
+        ));
+    }
+    let pool = load_pool(client, &pool_id)
+        .await?
+        .ok_or_else(|| anyhow!("Liquidity pool {} not found", pool_id))?;
+    let mut min_amounts = Vec::new();
+    let mut min_stroops = Vec::new();
+    for asset in &assets {
+        let amount = share_of(&reserve_amount(&pool, asset)?, &command.shares, &pool)?;
+        let amount = apply_slippage(&amount_string(amount)?, slippage, false)?;
+        min_stroops.push(amount.to_i64());
+        min_amounts.push(LiquidityPoolReserve {
+            asset: canonical_asset(asset),
+            amount: Amount::from_stroops(&amount)?.to_string(),
+        });
+    }
+    let operation = xdr::OperationBody::LiquidityPoolWithdraw(xdr::LiquidityPoolWithdrawOp {
+        liquidity_pool_id: network_pool_id(&pool_id)?,
+        amount: shares,
+        min_amount_a: min_stroops[0],
+        min_amount_b: min_stroops[1],
+    });
+    out.print(WithdrawPlan {
+        pool_id,
+        account_id: account.account_id(),
+        shares: Amount::from_str(&command.shares)?.to_string(),
+        min_amounts,
+    })
+    .map_err(Error::Convey)?;
+
+    let tx = raw_tx::build_transaction(client, &account, vec![operation]).await?;
+    raw_tx::sign_and_submit(out, client, &command.transaction, tx, &[keypair]).await
+}
+
+/// Returns the id of the constant product pool of the two assets, the hash of
+/// its parameters with the assets in the network order.
+pub fn pool_id(asset_a: &Asset, asset_b: &Asset) -> Result<String> {
+    let (first, second) = if is_sorted(asset_a, asset_b)? {
+        (asset_a, asset_b)
+    } else {
+        (asset_b, asset_a)
+    };
+    let mut parameters = LIQUIDITY_POOL_CONSTANT_PRODUCT.to_be_bytes().to_vec();
+    parameters.extend(first.xdr_bytes()?);
+    parameters.extend(second.xdr_bytes()?);
+    parameters.extend(&LIQUIDITY_POOL_FEE.to_be_bytes());
+    Ok(hex::encode(crypto::hash(&parameters)))
+}
+
+/// Returns true if the assets are in the order of the pool parameters.
+///
+/// The XDR encoding sorts the same way as the network: by asset type, then
+/// code, then issuer.
+fn is_sorted(asset_a: &Asset, asset_b: &Asset) -> Result<bool> {
+    let a = asset_a.xdr_bytes()?;
+    let b = asset_b.xdr_bytes()?;
+    if a == b {
+        return Err(anyhow!("A liquidity pool needs two different assets"));
+    }
+    Ok(a < b)
+}
+
+fn parse_pool_id(pool_id: &str) -> Result<String> {
+    match hex::decode(pool_id) {
+        Ok(bytes) if bytes.len() == 32 => Ok(pool_id.to_ascii_lowercase()),
+        _ => Err(anyhow!("Invalid liquidity pool id {}", pool_id)),
+    }
+}
+
+fn network_pool_id(pool_id: &str) -> Result<xdr::PoolId> {
+    let hash: [u8; 32] = hex::decode(pool_id)?
+        .try_into()
+        .map_err(|_| anyhow!("Invalid liquidity pool id {}", pool_id))?;
+    Ok(xdr::PoolId(xdr::Hash(hash)))
+}
+
+/// Returns `price` as the fraction the network expects.
+fn network_price(price: &Amount) -> Result<xdr::Price> {
+    if price.to_stroops()?.to_i64() <= 0 {
+        return Err(anyhow!(
+            "The price bound {} is too small, use a lower slippage",
+            price
+        ));
+    }
+    let fraction = Price::from_str(&price.to_string())?;
+    Ok(xdr::Price {
+        n: fraction.numerator(),
+        d: fraction.denominator(),
+    })
+}
+
+/// Returns the asset as Horizon formats it in liquidity pool reserves.
+fn canonical_asset(asset: &Asset) -> String {
+    match asset.as_credit() {
+        None => "native".to_string(),
+        Some(_) => format_asset(asset),
+    }
+}
+
+fn reserve_amount(pool: &LiquidityPool, asset: &Asset) -> Result<String> {
+    let asset = canonical_asset(asset);
+    pool.reserves
+        .iter()
+        .find(|reserve| reserve.asset == asset)
+        .map(|reserve| reserve.amount.clone())
+        .ok_or_else(|| anyhow!("Liquidity pool {} does not hold {}", pool.id, asset))
+}
+
+/// Returns the part of a reserve that `shares` can be redeemed for, in
+/// stroops.
+fn share_of(reserve: &str, shares: &str, pool: &LiquidityPool) -> Result<i128> {
+    let total = stroops(&pool.total_shares)? as i128;
+    if total == 0 {
+        return Ok(0);
+    }
+    Ok(stroops(reserve)? as i128 * stroops(shares)? as i128 / total)
+}
+
+/// Returns `a / b` with seven decimals, in stroops.
+fn ratio(a: i64, b: i64) -> Result<i64> {
+    if b == 0 {
+        return Err(anyhow!("Cannot compute the price of an empty reserve"));
+    }
+    Ok(i64::try_from(a as i128 * ONE / b as i128)?)
+}
+
+fn amount_string(amount: i128) -> Result<String> {
+    let amount = Amount::from_stroops(&Stroops::new(i64::try_from(amount)?))?;
+    Ok(amount.to_string())
+}
+
+async fn load_pool<H>(client: &H, pool_id: &str) -> Result<Option<LiquidityPool>>
+where
+    H: HorizonClient,
+{
+    let request = LiquidityPoolRequest {
+        pool_id: pool_id.to_string(),
+    };
+    match client.request(request).await {
+        Ok((_, pool)) => Ok(Some(pool)),
+        Err(HorizonError::HorizonRequestError(HorizonRequestError::Other(err)))
+            if err.status == 404 =>
+        {
+            Ok(None)
+        }
+        Err(err) => Err(err)?,
+    }
+}
+
+/// Returns the pool ids and amount of shares held by the account.
+async fn account_shares<H>(client: &H, account: &PublicKey) -> Result<Vec<(String, String)>>
+where
+    H: HorizonClient,
+{
+    let request = AccountBalancesRequest {
+        account_id: account.account_id(),
+    };
+    let (_, response) = client.request(request).await?;
+    Ok(response
+        .balances
+        .into_iter()
+        .filter(|balance| balance.asset_type == "liquidity_pool_shares")
+        .filter_map(|balance| Some((balance.liquidity_pool_id?, balance.balance)))
+        .collect())
+}
+
+fn format_reserve(reserve: &LiquidityPoolReserve) -> String {
+    let asset = if reserve.asset == "native" {
+        "XLM"
+    } else {
+        &reserve.asset
+    };
+    format!("{} {}", reserve.amount, asset)
+}
+
+fn reserve_code(reserve: &LiquidityPoolReserve) -> &str {
+    match reserve.asset.split(':').next() {
+        Some("native") | None => "XLM",
+        Some(code) => code,
+    }
+}
+
+fn render_lines(
+    mut fmt: &mut convey::human::Formatter,
+    lines: Vec<(&str, String)>,
+) -> std::result::Result<(), convey::Error> {
+    for (name, value) in lines {
+        text(name).render_for_humans(&mut fmt)?;
+        text("\t").render_for_humans(&mut fmt)?;
+        text(value).render_for_humans(&mut fmt)?;
+        newline().render_for_humans(&mut fmt)?;
+    }
+    Ok(())
+}
+
+impl Render for LiquidityPool {
+    fn render_for_humans(
+        &self,
+        fmt: &mut convey::human::Formatter,
+    ) -> std::result::Result<(), convey::Error> {
+        let mut lines = vec![("Pool", self.id.clone())];
+        for reserve in &self.reserves {
+            lines.push(("Reserve", format_reserve(reserve)));
+        }
+        lines.push(("Shares", self.total_shares.clone()));
+        lines.push(("Trustlines", self.total_trustlines.clone()));
+        lines.push(("Fee", format!("{} bp", self.fee_bp)));
+        render_lines(fmt, lines)
+    }
+
+    render_json!();
+}
+
+impl Render for LiquidityPoolList {
+    fn render_for_humans(
+        &self,
+        mut fmt: &mut convey::human::Formatter,
+    ) -> std::result::Result<(), convey::Error> {
+        for pool in &self.pools {
+            text(&pool.id).render_for_humans(&mut fmt)?;
+            for reserve in &pool.reserves {
+                text("\t").render_for_humans(&mut fmt)?;
+                text(format_reserve(reserve)).render_for_humans(&mut fmt)?;
+            }
+            text("\t").render_for_humans(&mut fmt)?;
+            text(format!("{} shares", pool.total_shares)).render_for_humans(&mut fmt)?;
+            newline().render_for_humans(&mut fmt)?;
+        }
+        Ok(())
+    }
+
+    render_json!();
+}
+
+impl Render for PoolSharesList {
+    fn render_for_humans(
+        &self,
+        mut fmt: &mut convey::human::Formatter,
+    ) -> std::result::Result<(), convey::Error> {
+        for pool in &self.pools {
+            text(&pool.pool_id).render_for_humans(&mut fmt)?;
+            text("\t").render_for_humans(&mut fmt)?;
+            text(format!("{} shares", pool.shares)).render_for_humans(&mut fmt)?;
+            for reserve in &pool.reserves {
+                text("\t").render_for_humans(&mut fmt)?;
+                text(format_reserve(reserve)).render_for_humans(&mut fmt)?;
+            }
+            newline().render_for_humans(&mut fmt)?;
+        }
+        Ok(())
+    }
+
+    render_json!();
+}
+
+impl Render for PoolId {
+    render_for_humans!(self -> [text(&self.id), newline(),]);
+
+    render_json!();
+}
+
+impl Render for DepositPlan {
+    fn render_for_humans(
+        &self,
+        fmt: &mut convey::human::Formatter,
+    ) -> std::result::Result<(), convey::Error> {
+        let mut lines = vec![
+            ("Pool", self.pool_id.clone()),
+            ("Account", self.account_id.clone()),
+        ];
+        for amount in &self.max_amounts {
+            lines.push(("Deposit at most", format_reserve(amount)));
+        }
+        // The price is the amount of the first asset for one of the second.
+        let unit = format!(
+            "{} per {}",
+            reserve_code(&self.max_amounts[0]),
+            reserve_code(&self.max_amounts[1])
+        );
+        lines.push(("Min price", format!("{} {}", self.min_price, unit)));
+        lines.push(("Max price", format!("{} {}", self.max_price, unit)));
+        render_lines(fmt, lines)
+    }
+
+    render_json!();
+}
+
+impl Render for WithdrawPlan {
+    fn render_for_humans(
+        &self,
+        fmt: &mut convey::human::Formatter,
+    ) -> std::result::Result<(), convey::Error> {
+        let mut lines = vec![
+            ("Pool", self.pool_id.clone()),
+            ("Account", self.account_id.clone()),
+            ("Shares", self.shares.clone()),
+        ];
+        for amount in &self.min_amounts {
+            lines.push(("Receive at least", format_reserve(amount)));
+        }
+        render_lines(fmt, lines)
+    }
+
+    render_json!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USDC: &str = "USDC:GA5ZSEJYB37JRC5AVCIA5MOP4RHTM335X2KGX3IHOJAPP5RE34K4KZVN";
+    const XLM_USDC_POOL: &str = "a468d41d8e9b8f3c7209651608b74b7db7ac9952dcae0cdf24871d1d9c7b0088";
+
+    #[test]
+    fn computes_pool_id_in_either_order() {
+        let xlm = parse_asset("XLM").unwrap();
+        let usdc = parse_asset(USDC).unwrap();
+        assert_eq!(pool_id(&xlm, &usdc).unwrap(), XLM_USDC_POOL);
+        assert_eq!(pool_id(&usdc, &xlm).unwrap(), XLM_USDC_POOL);
+    }
+
+    #[test]
+    fn sorts_native_before_credit_assets() {
+        let xlm = parse_asset("XLM").unwrap();
+        let usdc = parse_asset(USDC).unwrap();
+        assert!(is_sorted(&xlm, &usdc).unwrap());
+        assert!(!is_sorted(&usdc, &xlm).unwrap());
+        assert!(is_sorted(&xlm, &xlm).is_err());
+    }
+
+    #[test]
+    fn converts_price_bounds_to_fractions() {
+        let price = network_price(&Amount::from_str("1.05").unwrap()).unwrap();
+        assert_eq!((price.n, price.d), (21, 20));
+        assert_eq!(
+            network_price(&Amount::from_str("0").unwrap())
+                .unwrap_err()
+                .to_string(),
+            "The price bound 0.0000000 is too small, use a lower slippage"
+        );
+    }
+}
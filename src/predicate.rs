@@ -0,0 +1,165 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use stellar_base::claim::ClaimPredicate;
+use stellar_horizon::resources::Predicate;
+
+enum Time {
+    Absolute(DateTime<Utc>),
+    Relative(Duration),
+}
+
+/// Parses a claim predicate.
+///
+/// The syntax is `unconditional`, `before(TIME)`, `after(TIME)`, `not(P)`,
+/// `and(P, P, ...)` and `or(P, P, ...)`. `TIME` is either an absolute time,
+/// in RFC 3339 or `YYYY-MM-DD` format, or a time relative to the balance
+/// creation like `+30d`.
+pub fn parse_predicate(predicate: &str) -> Result<ClaimPredicate> {
+    let predicate = predicate.trim();
+    if predicate == "unconditional" {
+        return Ok(ClaimPredicate::new_unconditional());
+    }
+    let open = predicate
+        .find('(')
+        .filter(|_| predicate.ends_with(')'))
+        .ok_or_else(|| anyhow!("Invalid predicate {}", predicate))?;
+    let name = predicate[..open].trim();
+    let args = &predicate[open + 1..predicate.len() - 1];
+    match name {
+        "before" => Ok(before(parse_time(args)?)),
+        "after" => Ok(ClaimPredicate::new_not(before(parse_time(args)?))),
+        "not" => Ok(ClaimPredicate::new_not(parse_predicate(args)?)),
+        "and" => fold_predicates(args, ClaimPredicate::new_and),
+        "or" => fold_predicates(args, ClaimPredicate::new_or),
+        _ => Err(anyhow!("Invalid predicate {}", predicate)),
+    }
+}
+
+/// Returns true if a claimant with `predicate` can claim the balance at `time`.
+pub fn is_claimable(predicate: &Predicate, time: &DateTime<Utc>) -> bool {
+    match predicate {
+        Predicate::Unconditional(_) => true,
+        Predicate::And(inner) => inner.iter().all(|p| is_claimable(p, time)),
+        Predicate::Or(inner) => inner.iter().any(|p| is_claimable(p, time)),
+        Predicate::Not(inner) => !is_claimable(inner, time),
+        Predicate::AbsBefore(before) => time < before,
+        // Relative times are converted to absolute times when the balance is
+        // created, so they never show up in existing balances.
+        Predicate::RelBefore(_) => true,
+    }
+}
+
+/// Describes `predicate` in plain language, for example
+/// "after 2026-11-01 and before 2027-01-01".
+pub fn describe_predicate(predicate: &Predicate) -> String {
+    match predicate {
+        Predicate::Unconditional(_) => "at any time".to_string(),
+        Predicate::And(inner) => describe_predicates(inner, " and "),
+        Predicate::Or(inner) => describe_predicates(inner, " or "),
+        Predicate::Not(inner) => match inner.as_ref() {
+            Predicate::AbsBefore(time) => format!("after {}", format_time(time)),
+            Predicate::RelBefore(seconds) => {
+                format!("{} seconds after creation", seconds)
+            }
+            Predicate::Unconditional(_) => "never".to_string(),
+            inner => format!("not ({})", describe_predicate(inner)),
+        },
+        Predicate::AbsBefore(time) => format!("before {}", format_time(time)),
+        Predicate::RelBefore(seconds) => format!("within {} seconds of creation", seconds),
+    }
+}
+
+fn describe_predicates(predicates: &[Box<Predicate>], separator: &str) -> String {
+    predicates
+        .iter()
+        .map(|p| match p.as_ref() {
+            Predicate::And(_) | Predicate::Or(_) => format!("({})", describe_predicate(p)),
+            p => describe_predicate(p),
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn format_time(time: &DateTime<Utc>) -> String {
+    if NaiveTime::from_hms_opt(0, 0, 0) == Some(time.time()) {
+        time.format("%Y-%m-%d").to_string()
+    } else {
+        time.to_rfc3339()
+    }
+}
+
+fn before(time: Time) -> ClaimPredicate {
+    match time {
+        Time::Absolute(datetime) => ClaimPredicate::new_before_absolute_time(datetime),
+        Time::Relative(duration) => ClaimPredicate::new_before_relative_time(duration),
+    }
+}
+
+fn fold_predicates<F>(args: &str, combine: F) -> Result<ClaimPredicate>
+where
+    F: Fn(ClaimPredicate, ClaimPredicate) -> ClaimPredicate,
+{
+    let mut predicates = split_arguments(args)
+        .into_iter()
+        .map(parse_predicate)
+        .collect::<Result<Vec<_>>>()?;
+    if predicates.len() < 2 {
+        return Err(anyhow!("Expected at least two predicates in {}", args));
+    }
+    let mut result = predicates.pop().unwrap();
+    while let Some(predicate) = predicates.pop() {
+        result = combine(predicate, result);
+    }
+    Ok(result)
+}
+
+fn split_arguments(args: &str) -> Vec<&str> {
+    let mut result = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in args.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                result.push(&args[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    result.push(&args[start..]);
+    result
+}
+
+fn parse_time(time: &str) -> Result<Time> {
+    let time = time.trim();
+    if let Some(relative) = time.strip_prefix('+') {
+        return Ok(Time::Relative(parse_duration(relative)?));
+    }
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(time) {
+        return Ok(Time::Absolute(datetime.with_timezone(&Utc)));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(time, "%Y-%m-%d") {
+        let midnight = date
+            .and_hms_opt(0, 0, 0)
+            .ok_or_else(|| anyhow!("Invalid time {}", time))?;
+        return Ok(Time::Absolute(Utc.from_utc_datetime(&midnight)));
+    }
+    Err(anyhow!("Invalid time {}", time))
+}
+
+fn parse_duration(duration: &str) -> Result<Duration> {
+    let split = duration
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(duration.len());
+    let value: i64 = duration[..split].parse()?;
+    match &duration[split..] {
+        "" | "s" => Ok(Duration::seconds(value)),
+        "m" => Ok(Duration::minutes(value)),
+        "h" => Ok(Duration::hours(value)),
+        "d" => Ok(Duration::days(value)),
+        "w" => Ok(Duration::weeks(value)),
+        unit => Err(anyhow!("Invalid time unit {}", unit)),
+    }
+}
@@ -0,0 +1,283 @@
+//! Transactions written directly in the network XDR, for the operations
+//! stellar-base cannot build: clawbacks, trustline flags and liquidity pools.
+use crate::error::Error;
+use crate::fee;
+use crate::horizon::execute_and_print_request;
+use crate::wallet::{self, TransactionOptions, TransactionXdr};
+use anyhow::Result;
+use convey::Output;
+use std::convert::TryInto;
+use stellar_base::amount::Stroops;
+use stellar_base::crypto;
+use stellar_base::network::Network;
+use stellar_base::transaction::MIN_BASE_FEE;
+use stellar_base::xdr::XDRSerialize;
+use stellar_base::{Asset, KeyPair, Operation, PublicKey};
+use stellar_horizon::client::HorizonClient;
+use stellar_horizon::request::Request;
+use stellar_horizon::resources;
+use stellar_xdr::curr as xdr;
+use stellar_xdr::curr::{Limits, ReadXdr, WriteXdr};
+use url::Url;
+
+/// A transaction with operations in the network XDR, and its signatures.
+pub struct RawTransaction {
+    tx: xdr::Transaction,
+    signatures: Vec<xdr::DecoratedSignature>,
+}
+
+impl RawTransaction {
+    pub fn new(
+        source: &PublicKey,
+        sequence: i64,
+        operations: Vec<xdr::OperationBody>,
+    ) -> Result<RawTransaction> {
+        let operations: Vec<xdr::Operation> = operations
+            .into_iter()
+            .map(|body| xdr::Operation {
+                source_account: None,
+                body,
+            })
+            .collect();
+        let fee = MIN_BASE_FEE.to_i64() * operations.len() as i64;
+        let tx = xdr::Transaction {
+            source_account: xdr::MuxedAccount::Ed25519(uint256(source)?),
+            fee: fee.try_into()?,
+            seq_num: xdr::SequenceNumber(sequence),
+            cond: xdr::Preconditions::None,
+            memo: xdr::Memo::None,
+            operations: operations.try_into()?,
+            ext: xdr::TransactionExt::V0,
+        };
+        Ok(RawTransaction {
+            tx,
+            signatures: Vec::new(),
+        })
+    }
+
+    pub fn fee(&self) -> Stroops {
+        Stroops::new(self.tx.fee.into())
+    }
+
+    pub fn operations_count(&self) -> usize {
+        self.tx.operations.len()
+    }
+
+    pub fn set_fee(&mut self, fee: Stroops) -> Result<()> {
+        self.tx.fee = fee.to_i64().try_into()?;
+        Ok(())
+    }
+
+    pub fn hash(&self, network: &Network) -> Result<Vec<u8>> {
+        let network_id: [u8; 32] = network.network_id().as_slice().try_into()?;
+        let payload = xdr::TransactionSignaturePayload {
+            network_id: xdr::Hash(network_id),
+            tagged_transaction: xdr::TransactionSignaturePayloadTaggedTransaction::Tx(
+                self.tx.clone(),
+            ),
+        };
+        Ok(crypto::hash(&payload.to_xdr(Limits::none())?))
+    }
+
+    pub fn sign(&mut self, keypair: &KeyPair, network: &Network) -> Result<()> {
+        let hash = self.hash(network)?;
+        let key = keypair.public_key().as_bytes();
+        let hint: [u8; 4] = key[key.len() - 4..].try_into()?;
+        let signature = keypair.sign(&hash).to_vec();
+        self.signatures.push(xdr::DecoratedSignature {
+            hint: xdr::SignatureHint(hint),
+            signature: xdr::Signature(signature.try_into()?),
+        });
+        Ok(())
+    }
+
+    pub fn xdr_base64(&self) -> Result<String> {
+        let envelope = xdr::TransactionEnvelope::Tx(xdr::TransactionV1Envelope {
+            tx: self.tx.clone(),
+            signatures: self.signatures.clone().try_into()?,
+        });
+        Ok(envelope.to_xdr_base64(Limits::none())?)
+    }
+}
+
+/// Horizon request submitting a transaction from its XDR.
+struct SubmitRawTransactionRequest {
+    xdr: String,
+}
+
+impl Request for SubmitRawTransactionRequest {
+    type Response = resources::Transaction;
+
+    fn post_body(&self) -> stellar_horizon::error::Result<Option<String>> {
+        let body = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("tx", &self.xdr)
+            .finish();
+        Ok(Some(body))
+    }
+
+    fn uri(&self, host: &Url) -> stellar_horizon::error::Result<Url> {
+        Ok(host.join("/transactions")?)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct AccountSequence {
+    sequence: String,
+}
+
+/// Horizon request for an account, reading only its sequence since the
+/// account resource of stellar-horizon cannot hold pool shares.
+struct AccountSequenceRequest {
+    account_id: String,
+}
+
+impl Request for AccountSequenceRequest {
+    type Response = AccountSequence;
+
+    fn uri(&self, host: &Url) -> stellar_horizon::error::Result<Url> {
+        Ok(host.join(&format!("/accounts/{}", self.account_id))?)
+    }
+}
+
+/// Converts an operation built by stellar-base.
+pub fn operation(operation: &Operation) -> Result<xdr::OperationBody> {
+    let operation = xdr::Operation::from_xdr(operation.xdr_bytes()?, Limits::none())?;
+    Ok(operation.body)
+}
+
+/// Converts an asset of stellar-base.
+pub fn asset(asset: &Asset) -> Result<xdr::Asset> {
+    Ok(xdr::Asset::from_xdr(asset.xdr_bytes()?, Limits::none())?)
+}
+
+/// Returns a set options operation setting the account `flags`.
+pub fn set_flags(flags: u32) -> xdr::OperationBody {
+    xdr::OperationBody::SetOptions(xdr::SetOptionsOp {
+        inflation_dest: None,
+        clear_flags: None,
+        set_flags: Some(flags),
+        master_weight: None,
+        low_threshold: None,
+        med_threshold: None,
+        high_threshold: None,
+        home_domain: None,
+        signer: None,
+    })
+}
+
+pub fn muxed_account(key: &PublicKey) -> Result<xdr::MuxedAccount> {
+    Ok(xdr::MuxedAccount::Ed25519(uint256(key)?))
+}
+
+fn uint256(key: &PublicKey) -> Result<xdr::Uint256> {
+    Ok(xdr::Uint256(key.as_bytes().try_into()?))
+}
+
+pub async fn build_transaction<H>(
+    client: &H,
+    source: &PublicKey,
+    operations: Vec<xdr::OperationBody>,
+) -> Result<RawTransaction>
+where
+    H: HorizonClient,
+{
+    let request = AccountSequenceRequest {
+        account_id: source.account_id(),
+    };
+    let (_, account) = client.request(request).await?;
+    let sequence: i64 = account.sequence.parse()?;
+    RawTransaction::new(source, sequence + 1, operations)
+}
+
+/// Sets the fee of `tx` like `fee::apply_fee_strategy`.
+pub async fn apply_fee_strategy<H>(
+    out: &mut Output,
+    client: &H,
+    options: &TransactionOptions,
+    tx: &mut RawTransaction,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    if options.fee_strategy.is_none() && options.fee_cap.is_none() {
+        return Ok(());
+    }
+    let operations = tx.operations_count().max(1) as i64;
+    let base_fee =
+        fee::choose_base_fee(out, client, options, tx.fee().to_i64() / operations).await?;
+    tx.set_fee(Stroops::new(base_fee * operations))
+}
+
+pub async fn sign_and_submit<H>(
+    mut out: &mut Output,
+    client: &H,
+    options: &TransactionOptions,
+    mut tx: RawTransaction,
+    signers: &[KeyPair],
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let network = wallet::network(client).await?;
+    apply_fee_strategy(&mut out, client, options, &mut tx).await?;
+    for signer in signers {
+        tx.sign(signer, &network)?;
+    }
+    submit(&mut out, client, options, &tx).await
+}
+
+/// Submits `tx` like `wallet::submit_envelope`.
+pub async fn submit<H>(
+    mut out: &mut Output,
+    client: &H,
+    options: &TransactionOptions,
+    tx: &RawTransaction,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let xdr = tx.xdr_base64()?;
+    if options.no_submit {
+        out.print(TransactionXdr { xdr }).map_err(Error::Convey)?;
+        return Ok(());
+    }
+    execute_and_print_request(&mut out, client, SubmitRawTransactionRequest { xdr }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use stellar_base::amount::Amount;
+    use stellar_base::transaction::Transaction;
+
+    #[test]
+    fn matches_stellar_base_transactions() {
+        let keypair = KeyPair::random().unwrap();
+        let destination = KeyPair::random().unwrap();
+        let network = Network::new_test();
+        let payment = Operation::new_payment()
+            .with_destination(destination.public_key().clone())
+            .with_amount(Amount::from_str("10").unwrap())
+            .unwrap()
+            .with_asset(Asset::new_native())
+            .build()
+            .unwrap();
+
+        let mut expected = Transaction::builder(keypair.public_key().clone(), 42, MIN_BASE_FEE)
+            .add_operation(payment.clone())
+            .into_transaction()
+            .unwrap();
+        expected.sign(&keypair, &network).unwrap();
+        let mut tx =
+            RawTransaction::new(keypair.public_key(), 42, vec![operation(&payment).unwrap()])
+                .unwrap();
+        tx.sign(&keypair, &network).unwrap();
+
+        assert_eq!(tx.hash(&network).unwrap(), expected.hash(&network).unwrap());
+        assert_eq!(
+            tx.xdr_base64().unwrap(),
+            expected.into_envelope().xdr_base64().unwrap()
+        );
+    }
+}
@@ -0,0 +1,32 @@
+use anyhow::Result;
+use stellar_base::crypto::{
+    decode_pre_auth_tx, decode_sha256_hash, encode_pre_auth_tx, encode_sha256_hash,
+};
+use stellar_base::signature::{HashX, PreAuthTxHash, SignerKey};
+use stellar_base::PublicKey;
+
+/// Parses a signer key: an account id (G...), a pre authorized transaction
+/// hash (T...) or a sha256 hash (X...).
+pub fn parse_signer_key(key: &str) -> Result<SignerKey> {
+    match key.chars().next() {
+        Some('G') => Ok(SignerKey::new_from_public_key(PublicKey::from_account_id(
+            key,
+        )?)),
+        Some('T') => {
+            let hash = PreAuthTxHash::new(decode_pre_auth_tx(key)?)?;
+            Ok(SignerKey::new_from_pre_authorized_transaction(hash))
+        }
+        Some('X') => Ok(SignerKey::new_from_hashx(HashX::new(decode_sha256_hash(
+            key,
+        )?)?)),
+        _ => Err(anyhow!("Invalid signer key {}", key)),
+    }
+}
+
+pub fn format_signer_key(key: &SignerKey) -> String {
+    match key {
+        SignerKey::Ed25519(public_key) => public_key.account_id(),
+        SignerKey::PreAuthTx(hash) => encode_pre_auth_tx(hash.as_bytes()),
+        SignerKey::HashX(hash) => encode_sha256_hash(hash.as_bytes()),
+    }
+}
@@ -0,0 +1,317 @@
+use crate::claimable_balance::parse_balance_id;
+use crate::config::AppConfig;
+use crate::signer::parse_signer_key;
+use crate::trust::parse_credit_asset;
+use crate::wallet::{self, TransactionOptions};
+use anyhow::Result;
+use convey::Output;
+use std::str::FromStr;
+use stellar_base::account::DataValue;
+use stellar_base::amount::{Amount, Stroops};
+use stellar_base::ledger::LedgerKey;
+use stellar_base::signature::Signer;
+use stellar_base::{KeyPair, Operation, PublicKey};
+use stellar_horizon::client::HorizonClient;
+use stellar_horizon::resources::OfferId;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Sponsor the reserves of other accounts")]
+pub struct SponsorCommand {
+    #[structopt(short, long, global = true, help = "Which Horizon server to use")]
+    pub server: Option<String>,
+    #[structopt(subcommand)]
+    pub command: SponsorInnerCommand,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum SponsorInnerCommand {
+    #[structopt(about = "Sponsors the reserves of the entries created for an account")]
+    Reserves(SponsorReservesCommand),
+    #[structopt(about = "Revokes the sponsorship of a ledger entry or signer")]
+    Revoke(RevokeSponsorshipCommand),
+}
+
+#[derive(Debug, StructOpt)]
+pub struct SponsorReservesCommand {
+    #[structopt(
+        name = "SPONSOR",
+        help = "The sponsoring account id, starts with G, or its name"
+    )]
+    pub sponsor: String,
+    #[structopt(
+        name = "SPONSORED",
+        help = "The sponsored account id, starts with G, or its name"
+    )]
+    pub sponsored: String,
+    #[structopt(long, help = "Create the sponsored account")]
+    pub create_account: bool,
+    #[structopt(
+        long,
+        default_value = "0",
+        help = "The starting balance of the account created with --create-account"
+    )]
+    pub starting_balance: String,
+    #[structopt(
+        long,
+        number_of_values = 1,
+        help = "Add a trustline to ASSET, can be repeated. Use CODE:ISSUER."
+    )]
+    pub trust: Vec<String>,
+    #[structopt(
+        long,
+        number_of_values = 1,
+        help = "Add a data entry as NAME=VALUE, can be repeated"
+    )]
+    pub data: Vec<String>,
+    #[structopt(
+        long,
+        number_of_values = 1,
+        help = "Add a signer as KEY:WEIGHT, can be repeated"
+    )]
+    pub signer: Vec<String>,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct RevokeOptions {
+    #[structopt(
+        name = "SPONSOR",
+        help = "The current sponsor account id, starts with G, or its name"
+    )]
+    pub sponsor: String,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum RevokeSponsorshipCommand {
+    #[structopt(about = "Revokes the sponsorship of an account")]
+    Account {
+        #[structopt(flatten)]
+        options: RevokeOptions,
+        #[structopt(name = "ACCOUNT_ID", help = "The sponsored account id")]
+        account_id: String,
+    },
+    #[structopt(about = "Revokes the sponsorship of a trustline")]
+    Trustline {
+        #[structopt(flatten)]
+        options: RevokeOptions,
+        #[structopt(name = "ACCOUNT_ID", help = "The trustline account id")]
+        account_id: String,
+        #[structopt(name = "ASSET", help = "The trustline asset. Use CODE:ISSUER.")]
+        asset: String,
+    },
+    #[structopt(about = "Revokes the sponsorship of an offer")]
+    Offer {
+        #[structopt(flatten)]
+        options: RevokeOptions,
+        #[structopt(name = "ACCOUNT_ID", help = "The offer owner account id")]
+        account_id: String,
+        #[structopt(name = "OFFER_ID", help = "The offer id")]
+        offer_id: OfferId,
+    },
+    #[structopt(about = "Revokes the sponsorship of a data entry")]
+    Data {
+        #[structopt(flatten)]
+        options: RevokeOptions,
+        #[structopt(name = "ACCOUNT_ID", help = "The data entry account id")]
+        account_id: String,
+        #[structopt(name = "NAME", help = "The data entry name")]
+        name: String,
+    },
+    #[structopt(about = "Revokes the sponsorship of a claimable balance")]
+    ClaimableBalance {
+        #[structopt(flatten)]
+        options: RevokeOptions,
+        #[structopt(name = "CLAIMABLE_BALANCE_ID", help = "The claimable balance id")]
+        claimable_balance_id: String,
+    },
+    #[structopt(about = "Revokes the sponsorship of a signer")]
+    Signer {
+        #[structopt(flatten)]
+        options: RevokeOptions,
+        #[structopt(name = "ACCOUNT_ID", help = "The signer account id")]
+        account_id: String,
+        #[structopt(name = "SIGNER_KEY", help = "The signer key, starts with G, T or X")]
+        signer_key: String,
+    },
+}
+
+pub async fn run_command(
+    mut out: &mut Output,
+    config: &AppConfig,
+    command: SponsorCommand,
+) -> Result<()> {
+    let client = wallet::horizon_client(&config, &command.server)?;
+    match command.command {
+        SponsorInnerCommand::Reserves(cmd) => run_reserves(&mut out, &config, &client, cmd).await,
+        SponsorInnerCommand::Revoke(cmd) => run_revoke(&mut out, &config, &client, cmd).await,
+    }
+}
+
+pub async fn run_reserves<H>(
+    mut out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: SponsorReservesCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let sponsor = config.find_account(&command.sponsor)?.keypair()?;
+    let sponsored = config.resolve_public_key(&command.sponsored)?;
+    let mut operations = vec![Operation::new_begin_sponsoring_future_reserves()
+        .with_sponsored_id(sponsored.clone())
+        .build()?];
+    if command.create_account {
+        operations.push(
+            Operation::new_create_account()
+                .with_destination(sponsored.clone())
+                .with_starting_balance(Amount::from_str(&command.starting_balance)?)?
+                .build()?,
+        );
+    }
+    for asset in &command.trust {
+        operations.push(
+            Operation::new_change_trust()
+                .with_source_account(sponsored.clone())
+                .with_asset(parse_credit_asset(asset)?)
+                .with_limit(Some(Stroops::max()))?
+                .build()?,
+        );
+    }
+    for data in &command.data {
+        let (name, value) = split_pair(data, '=')?;
+        operations.push(
+            Operation::new_manage_data()
+                .with_source_account(sponsored.clone())
+                .with_data_name(name.to_string())
+                .with_data_value(Some(DataValue::from_slice(value.as_bytes())?))
+                .build()?,
+        );
+    }
+    for signer in &command.signer {
+        let (key, weight) = split_pair(signer, ':')?;
+        let signer = Signer::new(parse_signer_key(key)?, weight.parse()?);
+        operations.push(
+            Operation::new_set_options()
+                .with_source_account(sponsored.clone())
+                .with_signer(Some(signer))
+                .build()?,
+        );
+    }
+    if operations.len() == 1 {
+        return Err(anyhow!("No operations to sponsor"));
+    }
+    operations.push(
+        Operation::new_end_sponsoring_future_reserves()
+            .with_source_account(sponsored.clone())
+            .build(),
+    );
+    let signers = sponsorship_signers(config, &command.transaction, sponsor, &sponsored)?;
+    let tx = wallet::build_transaction(client, signers[0].public_key(), operations).await?;
+    wallet::sign_and_submit(&mut out, client, &command.transaction, tx, &signers).await
+}
+
+pub async fn run_revoke<H>(
+    mut out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: RevokeSponsorshipCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let builder = Operation::new_revoke_sponsorship();
+    let (options, builder) = match command {
+        RevokeSponsorshipCommand::Account {
+            options,
+            account_id,
+        } => {
+            let account = config.resolve_public_key(&account_id)?;
+            (
+                options,
+                builder.with_ledger_key(LedgerKey::Account(account)),
+            )
+        }
+        RevokeSponsorshipCommand::Trustline {
+            options,
+            account_id,
+            asset,
+        } => {
+            let account = config.resolve_public_key(&account_id)?;
+            let asset = parse_credit_asset(&asset)?;
+            let key = LedgerKey::Trustline(account, asset);
+            (options, builder.with_ledger_key(key))
+        }
+        RevokeSponsorshipCommand::Offer {
+            options,
+            account_id,
+            offer_id,
+        } => {
+            let account = config.resolve_public_key(&account_id)?;
+            let key = LedgerKey::Offer(account, offer_id);
+            (options, builder.with_ledger_key(key))
+        }
+        RevokeSponsorshipCommand::Data {
+            options,
+            account_id,
+            name,
+        } => {
+            let account = config.resolve_public_key(&account_id)?;
+            let key = LedgerKey::Data(account, name);
+            (options, builder.with_ledger_key(key))
+        }
+        RevokeSponsorshipCommand::ClaimableBalance {
+            options,
+            claimable_balance_id,
+        } => {
+            let balance_id = parse_balance_id(&claimable_balance_id)?;
+            let key = LedgerKey::ClaimableBalance(balance_id);
+            (options, builder.with_ledger_key(key))
+        }
+        RevokeSponsorshipCommand::Signer {
+            options,
+            account_id,
+            signer_key,
+        } => {
+            let account = config.resolve_public_key(&account_id)?;
+            let signer_key = parse_signer_key(&signer_key)?;
+            (options, builder.with_signer(account, signer_key))
+        }
+    };
+    let sponsor = config.find_account(&options.sponsor)?.keypair()?;
+    let operation = builder.build()?;
+    let tx = wallet::build_transaction(client, sponsor.public_key(), vec![operation]).await?;
+    wallet::sign_and_submit(&mut out, client, &options.transaction, tx, &[sponsor]).await
+}
+
+/// Returns the keys signing the sponsorship transaction, the sponsor first.
+///
+/// The sponsored account must sign too. If its secret seed is not available
+/// the transaction can only be printed, so that it can be signed elsewhere.
+fn sponsorship_signers(
+    config: &AppConfig,
+    options: &TransactionOptions,
+    sponsor: KeyPair,
+    sponsored: &PublicKey,
+) -> Result<Vec<KeyPair>> {
+    match config.find_account(&sponsored.account_id()) {
+        Ok(account) => Ok(vec![sponsor, account.keypair()?]),
+        Err(_) if options.no_submit => Ok(vec![sponsor]),
+        Err(_) => Err(anyhow!(
+            "Account {} not found, use --no-submit to collect its signature separately",
+            sponsored
+        )),
+    }
+}
+
+fn split_pair(value: &str, separator: char) -> Result<(&str, &str)> {
+    let mut parts = value.splitn(2, separator);
+    match (parts.next(), parts.next()) {
+        (Some(first), Some(second)) => Ok((first, second)),
+        _ => Err(anyhow!("Invalid value {}", value)),
+    }
+}
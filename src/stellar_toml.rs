@@ -0,0 +1,115 @@
+use crate::http;
+use anyhow::Result;
+use stellar_base::{Asset, PublicKey};
+
+/// The fields of a stellar.toml file used by aurora, as described in SEP-1.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct StellarToml {
+    pub network_passphrase: Option<String>,
+    pub signing_key: Option<String>,
+    pub uri_request_signing_key: Option<String>,
+    pub web_auth_endpoint: Option<String>,
+    pub transfer_server: Option<String>,
+    pub anchor_quote_server: Option<String>,
+    #[serde(default)]
+    pub currencies: Vec<Currency>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Currency {
+    pub code: Option<String>,
+    pub issuer: Option<String>,
+}
+
+/// Returns the base url of `domain`, which defaults to https if it has no
+/// scheme.
+pub fn domain_url(domain: &str) -> String {
+    let domain = domain.trim_end_matches('/');
+    if domain.starts_with("http://") || domain.starts_with("https://") {
+        domain.to_string()
+    } else {
+        format!("https://{}", domain)
+    }
+}
+
+/// Returns `domain` without its scheme, as used in home domains.
+pub fn domain_host(domain: &str) -> &str {
+    domain
+        .trim_start_matches("http://")
+        .trim_start_matches("https://")
+        .trim_end_matches('/')
+}
+
+/// Fetches and parses the stellar.toml file of `domain`.
+pub async fn fetch(domain: &str) -> Result<StellarToml> {
+    let url = format!("{}/.well-known/stellar.toml", domain_url(domain));
+    let body = http::get(&url, None).await?;
+    let content = String::from_utf8(body).map_err(|_| anyhow!("Invalid stellar.toml {}", url))?;
+    toml::from_str(&content).map_err(|err| anyhow!("Invalid stellar.toml {}: {}", url, err))
+}
+
+impl StellarToml {
+    pub fn signing_key(&self, domain: &str) -> Result<PublicKey> {
+        parse_key(domain, "SIGNING_KEY", &self.signing_key)
+    }
+
+    pub fn uri_request_signing_key(&self, domain: &str) -> Result<PublicKey> {
+        parse_key(
+            domain,
+            "URI_REQUEST_SIGNING_KEY",
+            &self.uri_request_signing_key,
+        )
+    }
+
+    pub fn web_auth_endpoint(&self, domain: &str) -> Result<&str> {
+        require(domain, "WEB_AUTH_ENDPOINT", &self.web_auth_endpoint)
+    }
+
+    pub fn transfer_server(&self, domain: &str) -> Result<&str> {
+        require(domain, "TRANSFER_SERVER", &self.transfer_server)
+    }
+
+    pub fn anchor_quote_server(&self, domain: &str) -> Result<&str> {
+        require(domain, "ANCHOR_QUOTE_SERVER", &self.anchor_quote_server)
+    }
+
+    /// Returns the asset with `code` listed in the CURRENCIES.
+    pub fn currency(&self, domain: &str, code: &str) -> Result<Asset> {
+        let currency = self
+            .currencies
+            .iter()
+            .find(|c| c.code.as_deref() == Some(code))
+            .ok_or_else(|| anyhow!("The stellar.toml of {} does not list {}", domain, code))?;
+        match &currency.issuer {
+            Some(issuer) => Ok(Asset::new_credit(
+                code,
+                PublicKey::from_account_id(issuer)?,
+            )?),
+            None => Err(anyhow!(
+                "The stellar.toml of {} has no issuer for {}",
+                domain,
+                code
+            )),
+        }
+    }
+}
+
+fn require<'a>(domain: &str, field: &str, value: &'a Option<String>) -> Result<&'a str> {
+    value
+        .as_deref()
+        .map(|v| v.trim_end_matches('/'))
+        .ok_or_else(|| anyhow!("The stellar.toml of {} has no {}", domain, field))
+}
+
+fn parse_key(domain: &str, field: &str, value: &Option<String>) -> Result<PublicKey> {
+    let key = require(domain, field, value)?;
+    PublicKey::from_account_id(key).map_err(|_| {
+        anyhow!(
+            "Invalid {} {} in the stellar.toml of {}",
+            field,
+            key,
+            domain
+        )
+    })
+}
@@ -0,0 +1,218 @@
+use crate::asset::{find_balance, format_resource_asset, parse_asset};
+use crate::config::AppConfig;
+use crate::error::Error;
+use crate::wallet::{self, TransactionOptions};
+use anyhow::Result;
+use convey::components::{newline, text};
+use convey::{Output, Render};
+use std::str::FromStr;
+use stellar_base::amount::{Amount, Stroops};
+use stellar_base::{Asset, Operation, PublicKey};
+use stellar_horizon::client::HorizonClient;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Manage trustlines")]
+pub struct TrustCommand {
+    #[structopt(short, long, global = true, help = "Which Horizon server to use")]
+    pub server: Option<String>,
+    #[structopt(subcommand)]
+    pub command: TrustInnerCommand,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum TrustInnerCommand {
+    #[structopt(about = "Creates or updates a trustline")]
+    Add(AddTrustCommand),
+    #[structopt(about = "Removes a trustline with zero balance")]
+    Remove(RemoveTrustCommand),
+    #[structopt(about = "Lists all trustlines of an account")]
+    List(ListTrustCommand),
+}
+
+#[derive(Debug, StructOpt)]
+pub struct AddTrustCommand {
+    #[structopt(name = "ACCOUNT_ID", help = "The trusting account id, starts with G")]
+    pub account_id: String,
+    #[structopt(
+        name = "ASSET",
+        help = "The asset to trust. Use CODE:ISSUER to identify the asset."
+    )]
+    pub asset: String,
+    #[structopt(long, help = "The trustline limit, defaults to the maximum amount")]
+    pub limit: Option<String>,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct RemoveTrustCommand {
+    #[structopt(name = "ACCOUNT_ID", help = "The trusting account id, starts with G")]
+    pub account_id: String,
+    #[structopt(
+        name = "ASSET",
+        help = "The asset to stop trusting. Use CODE:ISSUER to identify the asset."
+    )]
+    pub asset: String,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ListTrustCommand {
+    #[structopt(name = "ACCOUNT_ID", help = "The Stellar account id, starts with G")]
+    pub account_id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Trustline {
+    pub asset: String,
+    pub balance: String,
+    pub limit: Option<String>,
+    pub is_authorized: bool,
+    pub is_authorized_to_maintain_liabilities: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TrustlineList {
+    pub trustlines: Vec<Trustline>,
+}
+
+pub async fn run_command(
+    mut out: &mut Output,
+    config: &AppConfig,
+    command: TrustCommand,
+) -> Result<()> {
+    let client = wallet::horizon_client(&config, &command.server)?;
+    match command.command {
+        TrustInnerCommand::Add(cmd) => run_add(&mut out, &config, &client, cmd).await,
+        TrustInnerCommand::Remove(cmd) => run_remove(&mut out, &config, &client, cmd).await,
+        TrustInnerCommand::List(cmd) => run_list(&mut out, &config, &client, cmd).await,
+    }
+}
+
+pub async fn run_add<H>(
+    mut out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: AddTrustCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let keypair = config.find_account(&command.account_id)?.keypair()?;
+    let asset = parse_credit_asset(&command.asset)?;
+    let limit = match command.limit {
+        None => Stroops::max(),
+        Some(limit) => Amount::from_str(&limit)?.to_stroops()?,
+    };
+    let operation = Operation::new_change_trust()
+        .with_asset(asset)
+        .with_limit(Some(limit))?
+        .build()?;
+    let tx = wallet::build_transaction(client, keypair.public_key(), vec![operation]).await?;
+    wallet::sign_and_submit(&mut out, client, &command.transaction, tx, &[keypair]).await
+}
+
+pub async fn run_remove<H>(
+    mut out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: RemoveTrustCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let keypair = config.find_account(&command.account_id)?.keypair()?;
+    let asset = parse_credit_asset(&command.asset)?;
+    let account = wallet::load_account(client, keypair.public_key()).await?;
+    let balance = find_balance(&account, &asset)
+        .ok_or_else(|| anyhow!("Account does not trust {}", command.asset))?;
+    if Amount::from_str(&balance.balance)?.to_stroops()?.to_i64() != 0 {
+        return Err(anyhow!(
+            "Cannot remove trustline with non-zero balance {}",
+            balance.balance
+        ));
+    }
+    let operation = Operation::new_change_trust()
+        .with_asset(asset)
+        .with_limit::<Stroops>(None)?
+        .build()?;
+    let tx = wallet::build_transaction(client, keypair.public_key(), vec![operation]).await?;
+    wallet::sign_and_submit(&mut out, client, &command.transaction, tx, &[keypair]).await
+}
+
+pub async fn run_list<H>(
+    out: &mut Output,
+    _config: &AppConfig,
+    client: &H,
+    command: ListTrustCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let account_id = PublicKey::from_account_id(&command.account_id)?;
+    let account = wallet::load_account(client, &account_id).await?;
+    let trustlines = account
+        .balances
+        .iter()
+        .filter(|b| b.asset.asset_type != "native")
+        .map(|b| Trustline {
+            asset: format_resource_asset(&b.asset),
+            balance: b.balance.clone(),
+            limit: b.limit.clone(),
+            is_authorized: b.is_authorized.unwrap_or(false),
+            is_authorized_to_maintain_liabilities: b
+                .is_authorized_to_maintain_liabilities
+                .unwrap_or(false),
+        })
+        .collect();
+    out.print(TrustlineList { trustlines })
+        .map_err(Error::Convey)?;
+    Ok(())
+}
+
+pub fn parse_credit_asset(asset: &str) -> Result<Asset> {
+    let asset = parse_asset(asset)?;
+    if asset.is_native() {
+        return Err(anyhow!("Cannot change trust for the native asset"));
+    }
+    Ok(asset)
+}
+
+impl Trustline {
+    fn authorization(&self) -> &str {
+        if self.is_authorized {
+            "authorized"
+        } else if self.is_authorized_to_maintain_liabilities {
+            "authorized to maintain liabilities"
+        } else {
+            "unauthorized"
+        }
+    }
+}
+
+impl Render for Trustline {
+    render_for_humans!(self -> [
+        text(&self.asset), text("\t"),
+        text(&self.balance), text("\t"),
+        text(self.limit.as_deref().unwrap_or("-")), text("\t"),
+        text(self.authorization()), newline(),
+    ]);
+
+    render_json!();
+}
+
+impl Render for TrustlineList {
+    fn render_for_humans(
+        &self,
+        mut fmt: &mut convey::human::Formatter,
+    ) -> std::result::Result<(), convey::Error> {
+        for trustline in &self.trustlines {
+            trustline.render_for_humans(&mut fmt)?;
+        }
+        Ok(())
+    }
+
+    render_json!();
+}
@@ -0,0 +1,437 @@
+use crate::asset::parse_asset;
+use crate::config::AppConfig;
+use crate::fee::FeeStrategy;
+use crate::offer::parse_price;
+use crate::predicate::parse_predicate;
+use crate::wallet::{self, MemoOptions, TransactionOptions};
+use anyhow::Result;
+use chrono::Duration;
+use convey::Output;
+use serde::de::{self, Deserialize, Deserializer};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+use stellar_base::account::DataValue;
+use stellar_base::amount::{Amount, Stroops};
+use stellar_base::claim::{ClaimPredicate, Claimant};
+use stellar_base::time_bounds::TimeBounds;
+use stellar_base::transaction::MIN_BASE_FEE;
+use stellar_base::{Asset, KeyPair, Operation, PublicKey};
+use stellar_horizon::client::HorizonClient;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct BuildCommand {
+    #[structopt(
+        long,
+        help = "The transaction spec file, TOML or JSON if it ends with .json"
+    )]
+    pub spec: String,
+    #[structopt(
+        long = "set",
+        number_of_values = 1,
+        help = "Set a spec variable as KEY=VALUE, can be repeated"
+    )]
+    pub variables: Vec<String>,
+    #[structopt(
+        long,
+        help = "Send even if the destination requires a memo and there is none"
+    )]
+    pub skip_memo_check: bool,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+/// A transaction described by a spec file.
+///
+/// Strings can reference the spec variables as `${name}`, accounts and
+/// assets can be given by their alias. Numbers can also be given as strings,
+/// so that they can come from variables.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct TransactionSpec {
+    source: String,
+    memo_text: Option<String>,
+    #[serde(default, deserialize_with = "optional_number")]
+    memo_id: Option<u64>,
+    #[serde(default, deserialize_with = "optional_number")]
+    base_fee: Option<i64>,
+    fee_strategy: Option<String>,
+    #[serde(default, deserialize_with = "optional_number")]
+    fee_cap: Option<i64>,
+    #[serde(default, deserialize_with = "optional_number")]
+    timeout: Option<i64>,
+    #[serde(default)]
+    accounts: HashMap<String, String>,
+    #[serde(default)]
+    assets: HashMap<String, String>,
+    operations: Vec<OperationSpec>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OperationSpec {
+    source: Option<String>,
+    #[serde(flatten)]
+    body: OperationBodySpec,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum OperationBodySpec {
+    CreateAccount {
+        destination: String,
+        starting_balance: String,
+    },
+    Payment {
+        destination: String,
+        amount: String,
+        #[serde(default = "native_asset")]
+        asset: String,
+    },
+    ChangeTrust {
+        asset: String,
+        limit: Option<String>,
+    },
+    ManageSellOffer {
+        selling: String,
+        buying: String,
+        amount: String,
+        price: String,
+        #[serde(default, deserialize_with = "optional_number")]
+        offer_id: Option<i64>,
+    },
+    ManageBuyOffer {
+        selling: String,
+        buying: String,
+        amount: String,
+        price: String,
+        #[serde(default, deserialize_with = "optional_number")]
+        offer_id: Option<i64>,
+    },
+    ManageData {
+        name: String,
+        value: Option<String>,
+    },
+    BumpSequence {
+        #[serde(deserialize_with = "number")]
+        bump_to: i64,
+    },
+    AccountMerge {
+        destination: String,
+    },
+    CreateClaimableBalance {
+        asset: String,
+        amount: String,
+        claimants: Vec<ClaimantSpec>,
+    },
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct ClaimantSpec {
+    destination: String,
+    predicate: Option<String>,
+}
+
+/// A spec number, given as is or as a string.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberSpec<T> {
+    Number(T),
+    String(String),
+}
+
+impl<T> NumberSpec<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    fn into_number<E: de::Error>(self) -> std::result::Result<T, E> {
+        match self {
+            NumberSpec::Number(number) => Ok(number),
+            NumberSpec::String(string) => string
+                .trim()
+                .parse()
+                .map_err(|err| E::custom(format!("invalid number {}: {}", string, err))),
+        }
+    }
+}
+
+pub async fn run_build<H>(
+    mut out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: BuildCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let spec = load_spec(&command.spec, &command.variables)?;
+    let source = spec.account(config, &spec.source)?;
+    let mut operations = Vec::new();
+    let mut signers = signer_keypair(config, &source)
+        .into_iter()
+        .collect::<Vec<_>>();
+    if signers.is_empty() && !command.transaction.no_submit {
+        return Err(anyhow!(
+            "Account {} not found, use --no-submit to collect its signature separately",
+            source
+        ));
+    }
+    for operation in &spec.operations {
+        let mut built = spec.operation(config, &operation.body)?;
+        if let Some(source) = &operation.source {
+            let source = spec.account(config, source)?;
+            if let Some(keypair) = signer_keypair(config, &source) {
+                if !signers
+                    .iter()
+                    .any(|s| s.public_key() == keypair.public_key())
+                {
+                    signers.push(keypair);
+                }
+            }
+            *built.source_account_mut() = Some(source.into());
+        }
+        operations.push(built);
+    }
+    if operations.is_empty() {
+        return Err(anyhow!("The transaction spec has no operations"));
+    }
+
+    let base_fee = spec.base_fee.unwrap_or_else(|| MIN_BASE_FEE.to_i64());
+    if base_fee < MIN_BASE_FEE.to_i64() {
+        return Err(anyhow!(
+            "The base fee {} is below the minimum base fee {}",
+            base_fee,
+            MIN_BASE_FEE.to_i64()
+        ));
+    }
+    // The command line fee options take precedence over the spec ones.
+    let mut options = command.transaction;
+    if options.fee_strategy.is_none() {
+        options.fee_strategy = match &spec.fee_strategy {
+            None => None,
+            Some(strategy) => Some(FeeStrategy::from_str(strategy)?),
+        };
+    }
+    options.fee_cap = options.fee_cap.or(spec.fee_cap);
+    let fee = base_fee * operations.len() as i64;
+    let memo = MemoOptions {
+        memo_text: spec.memo_text.clone(),
+        memo_id: spec.memo_id,
+    };
+    let mut tx = wallet::build_transaction(client, &source, operations).await?;
+    *tx.memo_mut() = memo.to_memo()?;
+    *tx.fee_mut() = Stroops::new(fee);
+    if let Some(timeout) = spec.timeout {
+        *tx.time_bounds_mut() = Some(TimeBounds::valid_for(Duration::seconds(timeout)));
+    }
+    if !command.skip_memo_check {
+        wallet::check_memo_required(client, &tx).await?;
+    }
+    wallet::sign_and_submit(&mut out, client, &options, tx, &signers).await
+}
+
+fn load_spec(path: &str, overrides: &[String]) -> Result<TransactionSpec> {
+    let content =
+        std::fs::read_to_string(path).map_err(|err| anyhow!("Cannot read {}: {}", path, err))?;
+    let mut value: Value = if path.ends_with(".json") {
+        serde_json::from_str(&content)?
+    } else {
+        serde_json::to_value(toml::from_str::<toml::Value>(&content)?)?
+    };
+    let mut variables = HashMap::new();
+    let defaults = value
+        .as_object_mut()
+        .and_then(|spec| spec.remove("variables"));
+    if let Some(Value::Object(defaults)) = defaults {
+        for (name, default) in defaults {
+            let default = match default {
+                Value::String(default) => default.clone(),
+                default => default.to_string(),
+            };
+            variables.insert(name, default);
+        }
+    }
+    for variable in overrides {
+        let mut parts = variable.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some(name), Some(value)) => variables.insert(name.to_string(), value.to_string()),
+            _ => return Err(anyhow!("Invalid variable {}, use KEY=VALUE", variable)),
+        };
+    }
+    substitute_variables(&mut value, &variables)?;
+    serde_json::from_value(value).map_err(|err| anyhow!("Invalid transaction spec: {}", err))
+}
+
+fn substitute_variables(value: &mut Value, variables: &HashMap<String, String>) -> Result<()> {
+    match value {
+        Value::String(string) => {
+            let mut result = String::new();
+            let mut rest = string.as_str();
+            while let Some(start) = rest.find("${") {
+                let end = rest[start..]
+                    .find('}')
+                    .ok_or_else(|| anyhow!("Unterminated variable in {}", string))?;
+                let name = &rest[start + 2..start + end];
+                let variable = variables
+                    .get(name)
+                    .ok_or_else(|| anyhow!("Undefined variable {}", name))?;
+                result.push_str(&rest[..start]);
+                result.push_str(variable);
+                rest = &rest[start + end + 1..];
+            }
+            result.push_str(rest);
+            *string = result;
+        }
+        Value::Array(array) => {
+            for item in array.iter_mut() {
+                substitute_variables(item, variables)?;
+            }
+        }
+        Value::Object(object) => {
+            for field in object.values_mut() {
+                substitute_variables(field, variables)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn number<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + FromStr,
+    T::Err: Display,
+{
+    NumberSpec::deserialize(deserializer)?.into_number()
+}
+
+fn optional_number<'de, D, T>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + FromStr,
+    T::Err: Display,
+{
+    match Option::<NumberSpec<T>>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(number) => Ok(Some(number.into_number()?)),
+    }
+}
+
+fn signer_keypair(config: &AppConfig, account: &PublicKey) -> Option<KeyPair> {
+    config
+        .find_account(&account.account_id())
+        .and_then(|account| account.keypair())
+        .ok()
+}
+
+fn native_asset() -> String {
+    "XLM".to_string()
+}
+
+impl TransactionSpec {
+    fn account(&self, config: &AppConfig, account: &str) -> Result<PublicKey> {
+        let account = self.accounts.get(account).map_or(account, |a| a.as_str());
+        config.resolve_public_key(account)
+    }
+
+    fn asset(&self, asset: &str) -> Result<Asset> {
+        let asset = self.assets.get(asset).map_or(asset, |a| a.as_str());
+        parse_asset(asset)
+    }
+
+    fn operation(&self, config: &AppConfig, operation: &OperationBodySpec) -> Result<Operation> {
+        let operation = match operation {
+            OperationBodySpec::CreateAccount {
+                destination,
+                starting_balance,
+            } => Operation::new_create_account()
+                .with_destination(self.account(config, destination)?)
+                .with_starting_balance(Amount::from_str(starting_balance)?)?
+                .build()?,
+            OperationBodySpec::Payment {
+                destination,
+                amount,
+                asset,
+            } => Operation::new_payment()
+                .with_destination(self.account(config, destination)?)
+                .with_amount(Amount::from_str(amount)?)?
+                .with_asset(self.asset(asset)?)
+                .build()?,
+            OperationBodySpec::ChangeTrust { asset, limit } => {
+                let limit = match limit {
+                    None => Stroops::max(),
+                    Some(limit) => Amount::from_str(limit)?.to_stroops()?,
+                };
+                Operation::new_change_trust()
+                    .with_asset(self.asset(asset)?)
+                    .with_limit(Some(limit))?
+                    .build()?
+            }
+            OperationBodySpec::ManageSellOffer {
+                selling,
+                buying,
+                amount,
+                price,
+                offer_id,
+            } => Operation::new_manage_sell_offer()
+                .with_selling_asset(self.asset(selling)?)
+                .with_buying_asset(self.asset(buying)?)
+                .with_amount(Amount::from_str(amount)?)?
+                .with_price(parse_price(price)?)
+                .with_offer_id(*offer_id)
+                .build()?,
+            OperationBodySpec::ManageBuyOffer {
+                selling,
+                buying,
+                amount,
+                price,
+                offer_id,
+            } => Operation::new_manage_buy_offer()
+                .with_selling_asset(self.asset(selling)?)
+                .with_buying_asset(self.asset(buying)?)
+                .with_buy_amount(Amount::from_str(amount)?)?
+                .with_price(parse_price(price)?)
+                .with_offer_id(*offer_id)
+                .build()?,
+            OperationBodySpec::ManageData { name, value } => {
+                let value = match value {
+                    None => None,
+                    Some(value) => Some(DataValue::from_slice(value.as_bytes())?),
+                };
+                Operation::new_manage_data()
+                    .with_data_name(name.clone())
+                    .with_data_value(value)
+                    .build()?
+            }
+            OperationBodySpec::BumpSequence { bump_to } => Operation::new_bump_sequence()
+                .with_bump_to(*bump_to)
+                .build()?,
+            OperationBodySpec::AccountMerge { destination } => Operation::new_account_merge()
+                .with_destination(self.account(config, destination)?.into())
+                .build()?,
+            OperationBodySpec::CreateClaimableBalance {
+                asset,
+                amount,
+                claimants,
+            } => {
+                let mut builder = Operation::new_create_claimable_balance()
+                    .with_asset(self.asset(asset)?)
+                    .with_amount(Amount::from_str(amount)?)?;
+                for claimant in claimants {
+                    let predicate = match &claimant.predicate {
+                        None => ClaimPredicate::new_unconditional(),
+                        Some(predicate) => parse_predicate(predicate)?,
+                    };
+                    let destination = self.account(config, &claimant.destination)?;
+                    builder = builder.add_claimant(Claimant::new(destination, predicate));
+                }
+                builder.build()?
+            }
+        };
+        Ok(operation)
+    }
+}
@@ -0,0 +1,585 @@
+use crate::asset::{find_balance, format_asset, format_resource_asset};
+use crate::config::AppConfig;
+use crate::error::Error;
+use crate::horizon::execute_and_print_request;
+use crate::tx::status::envelope_requirements;
+use crate::tx::{format_stroops, muxed_public_key, operation_name, read_envelope};
+use crate::wallet;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use convey::components::{newline, text};
+use convey::{Output, Render};
+use std::collections::HashMap;
+use stellar_base::amount::Amount;
+use stellar_base::crypto::MuxedAccount;
+use stellar_base::transaction::{Transaction, TransactionEnvelope};
+use stellar_base::{Asset, Network, Operation, PublicKey};
+use stellar_horizon::api;
+use stellar_horizon::client::HorizonClient;
+use stellar_horizon::resources;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct CheckCommand {
+    #[structopt(
+        name = "XDR",
+        help = "The transaction envelope, base64 encoded or a file containing it"
+    )]
+    pub xdr: String,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct SubmitCommand {
+    #[structopt(
+        name = "XDR",
+        help = "The transaction envelope, base64 encoded or a file containing it"
+    )]
+    pub xdr: String,
+    #[structopt(long, help = "Submit without checking the transaction first")]
+    pub skip_check: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TransactionCheck {
+    pub hash: String,
+    pub problems: Vec<String>,
+}
+
+/// The ledger state a transaction is checked against, with the accounts
+/// loaded from Horizon once each.
+struct Checker<'a, H: HorizonClient> {
+    client: &'a H,
+    accounts: HashMap<String, AccountState>,
+    base_reserve: i64,
+    close_time: DateTime<Utc>,
+    problems: Vec<String>,
+}
+
+/// An account as seen by the operations of the transaction.
+enum AccountState {
+    Missing,
+    Loaded(Box<resources::Account>),
+    /// Created by an earlier operation, with its starting balance in stroops
+    /// and the assets it trusted since.
+    Created {
+        balance: i64,
+        trustlines: Vec<String>,
+    },
+}
+
+pub async fn run_check<H>(
+    out: &mut Output,
+    _config: &AppConfig,
+    client: &H,
+    command: CheckCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let envelope = read_envelope(&command.xdr)?;
+    let network = wallet::network(client).await?;
+    let check = check_envelope(client, &envelope, &network).await?;
+    let problems = check.problems.len();
+    out.print(check).map_err(Error::Convey)?;
+    if problems > 0 {
+        return Err(anyhow!("The transaction has {} problems", problems));
+    }
+    Ok(())
+}
+
+pub async fn run_submit<H>(
+    mut out: &mut Output,
+    _config: &AppConfig,
+    client: &H,
+    command: SubmitCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let envelope = read_envelope(&command.xdr)?;
+    if command.skip_check {
+        let request = api::transactions::submit(&envelope)?;
+        return execute_and_print_request(&mut out, client, request).await;
+    }
+    let network = wallet::network(client).await?;
+    check_and_submit(&mut out, client, &envelope, &network).await
+}
+
+/// Checks `envelope` and submits it if it has no problems, printing them
+/// otherwise.
+pub async fn check_and_submit<H>(
+    mut out: &mut Output,
+    client: &H,
+    envelope: &TransactionEnvelope,
+    network: &Network,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let check = check_envelope(client, envelope, network).await?;
+    if !check.problems.is_empty() {
+        let problems = check.problems.len();
+        out.print(check).map_err(Error::Convey)?;
+        return Err(anyhow!(
+            "Not submitting, the transaction has {} problems. Use --skip-check to submit anyway.",
+            problems
+        ));
+    }
+    let request = api::transactions::submit(envelope)?;
+    execute_and_print_request(&mut out, client, request).await
+}
+
+/// Checks `envelope` against the current ledger state, collecting all the
+/// problems that would make it fail.
+pub async fn check_envelope<H>(
+    client: &H,
+    envelope: &TransactionEnvelope,
+    network: &Network,
+) -> Result<TransactionCheck>
+where
+    H: HorizonClient,
+{
+    let ledger = wallet::latest_ledger(client).await?;
+    let mut checker = Checker {
+        client,
+        accounts: HashMap::new(),
+        base_reserve: ledger.base_reserve_in_stroops.into(),
+        close_time: ledger.closed_at,
+        problems: Vec::new(),
+    };
+    let mut spending = HashMap::new();
+    let tx = match envelope {
+        TransactionEnvelope::Transaction(tx) => {
+            let source = muxed_public_key(tx.source_account());
+            add_spending(&mut spending, &source, tx.fee().to_i64());
+            tx
+        }
+        TransactionEnvelope::FeeBumpTransaction(fee_bump) => {
+            let fee_source = muxed_public_key(fee_bump.fee_source());
+            if !checker.exists(&fee_source).await? {
+                checker.problem(format!("Fee source {} does not exist", fee_source));
+            }
+            add_spending(&mut spending, &fee_source, fee_bump.fee().to_i64());
+            fee_bump.inner_transaction()
+        }
+    };
+    checker.check_transaction(tx, &mut spending).await?;
+    for (account_id, amount) in spending {
+        checker.check_balance(&account_id, amount).await?;
+    }
+    match envelope_requirements(client, envelope, network).await {
+        Ok(requirements) => {
+            for requirement in requirements.iter().filter(|r| !r.is_satisfied()) {
+                checker.problem(format!(
+                    "The {} of {} has signature weight {} of the required {}",
+                    requirement.item,
+                    requirement.account,
+                    requirement.signed_weight,
+                    requirement.required_weight
+                ));
+            }
+        }
+        Err(err) => checker.problem(format!("Cannot check the signatures: {}", err)),
+    }
+    Ok(TransactionCheck {
+        hash: hex::encode(envelope.hash(network)?),
+        problems: checker.problems,
+    })
+}
+
+fn add_spending(spending: &mut HashMap<String, i64>, account: &PublicKey, amount: i64) {
+    *spending.entry(account.account_id()).or_insert(0) += amount;
+}
+
+impl<'a, H> Checker<'a, H>
+where
+    H: HorizonClient,
+{
+    fn problem(&mut self, problem: String) {
+        self.problems.push(problem);
+    }
+
+    async fn state(&mut self, account_id: &PublicKey) -> Result<&AccountState> {
+        let key = account_id.account_id();
+        if !self.accounts.contains_key(&key) {
+            let state = match wallet::load_account_if_exists(self.client, account_id).await? {
+                None => AccountState::Missing,
+                Some(account) => AccountState::Loaded(Box::new(account)),
+            };
+            self.accounts.insert(key.clone(), state);
+        }
+        Ok(&self.accounts[&key])
+    }
+
+    async fn exists(&mut self, account_id: &PublicKey) -> Result<bool> {
+        Ok(!matches!(
+            self.state(account_id).await?,
+            AccountState::Missing
+        ))
+    }
+
+    async fn check_transaction(
+        &mut self,
+        tx: &Transaction,
+        spending: &mut HashMap<String, i64>,
+    ) -> Result<()> {
+        let source = muxed_public_key(tx.source_account());
+        match self.state(&source).await? {
+            AccountState::Missing => {
+                self.problem(format!("Source account {} does not exist", source))
+            }
+            AccountState::Created { .. } => {}
+            AccountState::Loaded(account) => {
+                let next_sequence = wallet::next_sequence(account)?;
+                if *tx.sequence() != next_sequence {
+                    self.problem(format!(
+                        "Sequence number {} does not match the next sequence number {} of {}",
+                        tx.sequence(),
+                        next_sequence,
+                        source
+                    ));
+                }
+            }
+        }
+        if let Some(time_bounds) = tx.time_bounds() {
+            if let Some(lower) = time_bounds.lower() {
+                if *lower > self.close_time {
+                    self.problem(format!("The transaction is not valid before {}", lower));
+                }
+            }
+            if let Some(upper) = time_bounds.upper() {
+                if *upper < self.close_time {
+                    self.problem(format!("The transaction expired at {}", upper));
+                }
+            }
+        }
+        for (index, operation) in tx.operations().iter().enumerate() {
+            let item = format!("operation {} {}", index, operation_name(operation));
+            let op_source = match operation.source_account() {
+                Some(account) => muxed_public_key(account),
+                None => source.clone(),
+            };
+            self.check_operation(&item, &op_source, operation, spending)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn check_operation(
+        &mut self,
+        item: &str,
+        source: &PublicKey,
+        operation: &Operation,
+        spending: &mut HashMap<String, i64>,
+    ) -> Result<()> {
+        if let Some(account) = operation.source_account() {
+            let account = muxed_public_key(account);
+            if !self.exists(&account).await? {
+                self.problem(format!("The {} source {} does not exist", item, account));
+            }
+        }
+        match operation {
+            Operation::CreateAccount(op) => {
+                if self.exists(op.destination()).await? {
+                    self.problem(format!(
+                        "The {} destination {} already exists",
+                        item,
+                        op.destination()
+                    ));
+                } else {
+                    // Later operations can use the account.
+                    self.accounts.insert(
+                        op.destination().account_id(),
+                        AccountState::Created {
+                            balance: op.starting_balance().to_i64(),
+                            trustlines: Vec::new(),
+                        },
+                    );
+                }
+                add_spending(spending, source, op.starting_balance().to_i64());
+            }
+            Operation::Payment(op) => {
+                self.check_destination(item, op.destination(), op.asset())
+                    .await?;
+                if op.asset().is_native() {
+                    add_spending(spending, source, op.amount().to_i64());
+                }
+            }
+            Operation::PathPaymentStrictSend(op) => {
+                self.check_destination(item, op.destination(), op.destination_asset())
+                    .await?;
+                if op.send_asset().is_native() {
+                    add_spending(spending, source, op.send_amount().to_i64());
+                }
+            }
+            Operation::PathPaymentStrictReceive(op) => {
+                self.check_destination(item, op.destination(), op.destination_asset())
+                    .await?;
+                if op.send_asset().is_native() {
+                    add_spending(spending, source, op.send_max().to_i64());
+                }
+            }
+            Operation::AccountMerge(op) => {
+                let destination = muxed_public_key(op.destination());
+                if !self.exists(&destination).await? {
+                    self.problem(format!(
+                        "The {} destination {} does not exist",
+                        item, destination
+                    ));
+                }
+            }
+            // A limit of zero, decoded as no limit, removes the trustline.
+            Operation::ChangeTrust(op) => {
+                let asset = format_asset(op.asset());
+                match self.accounts.get_mut(&source.account_id()) {
+                    Some(AccountState::Created { trustlines, .. }) => {
+                        if op.limit().is_some() {
+                            trustlines.push(asset);
+                        } else {
+                            trustlines.retain(|trustline| *trustline != asset);
+                        }
+                    }
+                    Some(AccountState::Loaded(account)) if op.limit().is_none() => {
+                        account
+                            .balances
+                            .retain(|balance| format_resource_asset(&balance.asset) != asset);
+                    }
+                    _ => {}
+                }
+            }
+            Operation::CreateClaimableBalance(op) if op.asset().is_native() => {
+                add_spending(spending, source, op.amount().to_i64());
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Checks that `destination` exists and can receive `asset`.
+    async fn check_destination(
+        &mut self,
+        item: &str,
+        destination: &MuxedAccount,
+        asset: &Asset,
+    ) -> Result<()> {
+        let destination = muxed_public_key(destination);
+        let issuer = asset.as_credit().map(|credit| credit.issuer().clone());
+        let problem = match self.state(&destination).await? {
+            AccountState::Missing => {
+                self.problem(format!(
+                    "The {} destination {} does not exist",
+                    item, destination
+                ));
+                return Ok(());
+            }
+            _ if issuer.is_none() || issuer == Some(destination.clone()) => None,
+            AccountState::Created { trustlines, .. } => {
+                if trustlines.contains(&format_asset(asset)) {
+                    None
+                } else {
+                    Some("has no trustline to")
+                }
+            }
+            AccountState::Loaded(account) => match find_balance(account, asset) {
+                None => Some("has no trustline to"),
+                Some(balance) if balance.is_authorized == Some(false) => {
+                    Some("is not authorized to hold")
+                }
+                Some(_) => None,
+            },
+        };
+        if let Some(problem) = problem {
+            self.problem(format!(
+                "The {} destination {} {} {}",
+                item,
+                destination,
+                problem,
+                format_asset(asset)
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks that `account_id` can spend `amount` stroops of XLM without
+    /// going below its minimum reserve.
+    async fn check_balance(&mut self, account_id: &str, amount: i64) -> Result<()> {
+        let base_reserve = self.base_reserve;
+        let public_key = PublicKey::from_account_id(account_id)?;
+        let account = match self.state(&public_key).await? {
+            AccountState::Missing => return Ok(()),
+            // The reserves of a new account may be sponsored, only its
+            // starting balance is known.
+            AccountState::Created { balance, .. } => {
+                let balance = *balance;
+                if amount > balance {
+                    self.problem(format!(
+                        "{} needs {} XLM but starts with {} XLM",
+                        account_id,
+                        format_stroops(amount),
+                        format_stroops(balance)
+                    ));
+                }
+                return Ok(());
+            }
+            AccountState::Loaded(account) => account,
+        };
+        let native = match find_balance(account, &Asset::new_native()) {
+            None => return Ok(()),
+            Some(native) => native,
+        };
+        let to_stroops =
+            |amount: &str| -> Result<i64> { Ok(amount.parse::<Amount>()?.to_stroops()?.to_i64()) };
+        let entries =
+            2 + i64::from(account.subentry_count) + account.num_sponsoring - account.num_sponsored;
+        let available = to_stroops(&native.balance)?
+            - to_stroops(&native.selling_liabilities)?
+            - entries * base_reserve;
+        if amount > available {
+            self.problem(format!(
+                "{} needs {} XLM but has {} XLM available above its minimum reserve",
+                account_id,
+                format_stroops(amount),
+                format_stroops(available.max(0))
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Render for TransactionCheck {
+    fn render_for_humans(
+        &self,
+        mut fmt: &mut convey::human::Formatter,
+    ) -> std::result::Result<(), convey::Error> {
+        let status = if self.problems.is_empty() {
+            "ok".to_string()
+        } else {
+            format!("{} problems", self.problems.len())
+        };
+        text(&self.hash).render_for_humans(&mut fmt)?;
+        text("\t").render_for_humans(&mut fmt)?;
+        text(status).render_for_humans(&mut fmt)?;
+        newline().render_for_humans(&mut fmt)?;
+        for problem in &self.problems {
+            text(problem).render_for_humans(&mut fmt)?;
+            newline().render_for_humans(&mut fmt)?;
+        }
+        Ok(())
+    }
+
+    render_json!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use stellar_base::amount::Stroops;
+    use stellar_base::KeyPair;
+    use stellar_horizon::client::HorizonHttpClient;
+
+    /// Returns the problems of `operations` sent by `source`, where `source`
+    /// and `holder` were created earlier in the transaction with a trustline
+    /// from `holder` to `asset`.
+    async fn problems(
+        source: &PublicKey,
+        holder: &PublicKey,
+        asset: &Asset,
+        operations: &[Operation],
+    ) -> Vec<String> {
+        // The accounts are all known, so Horizon is never queried.
+        let client = HorizonHttpClient::new_from_str("http://127.0.0.1:1").unwrap();
+        let mut accounts = HashMap::new();
+        for (account, trustlines) in &[(source, vec![]), (holder, vec![format_asset(asset)])] {
+            accounts.insert(
+                account.account_id(),
+                AccountState::Created {
+                    balance: 100_0000000,
+                    trustlines: trustlines.clone(),
+                },
+            );
+        }
+        let mut checker = Checker {
+            client: &client,
+            accounts,
+            base_reserve: 5000000,
+            close_time: Utc::now(),
+            problems: Vec::new(),
+        };
+        let mut spending = HashMap::new();
+        for (index, operation) in operations.iter().enumerate() {
+            let op_source = match operation.source_account() {
+                Some(account) => muxed_public_key(account),
+                None => source.clone(),
+            };
+            checker
+                .check_operation(&index.to_string(), &op_source, operation, &mut spending)
+                .await
+                .unwrap();
+        }
+        checker.problems
+    }
+
+    fn change_trust(holder: &PublicKey, asset: &Asset, limit: Option<Stroops>) -> Operation {
+        Operation::new_change_trust()
+            .with_source_account(holder.clone())
+            .with_asset(asset.clone())
+            .with_limit(limit)
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    fn payment(holder: &PublicKey, asset: &Asset) -> Operation {
+        Operation::new_payment()
+            .with_destination(holder.clone())
+            .with_amount(Amount::from_str("1").unwrap())
+            .unwrap()
+            .with_asset(asset.clone())
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn payment_after_removing_trustline_fails() {
+        let source = KeyPair::random().unwrap().public_key().clone();
+        let holder = KeyPair::random().unwrap().public_key().clone();
+        let issuer = KeyPair::random().unwrap().public_key().clone();
+        let asset = Asset::new_credit("USD", issuer).unwrap();
+        let problems = problems(
+            &source,
+            &holder,
+            &asset,
+            &[
+                change_trust(&holder, &asset, None),
+                payment(&holder, &asset),
+            ],
+        )
+        .await;
+        assert_eq!(
+            problems,
+            vec![format!(
+                "The 1 destination {} has no trustline to {}",
+                holder,
+                format_asset(&asset)
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn payment_after_adding_trustline_passes() {
+        let source = KeyPair::random().unwrap().public_key().clone();
+        let holder = KeyPair::random().unwrap().public_key().clone();
+        let issuer = KeyPair::random().unwrap().public_key().clone();
+        let asset = Asset::new_credit("USD", issuer).unwrap();
+        let other = Asset::new_credit("EUR", source.clone()).unwrap();
+        let operations = [
+            change_trust(&holder, &other, Some(Stroops::max())),
+            payment(&holder, &other),
+            payment(&holder, &asset),
+        ];
+        assert!(problems(&source, &holder, &asset, &operations)
+            .await
+            .is_empty());
+    }
+}
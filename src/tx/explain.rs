@@ -0,0 +1,488 @@
+use crate::asset::format_asset;
+use crate::config::AppConfig;
+use crate::error::Error;
+use crate::signer::format_signer_key;
+use crate::tx::{format_stroops, muxed_public_key, read_envelope};
+use anyhow::Result;
+use convey::components::{newline, text};
+use convey::{Output, Render};
+use stellar_base::account::{AccountFlags, TrustLineFlags};
+use stellar_base::amount::{Price, Stroops};
+use stellar_base::asset::CreditAssetType;
+use stellar_base::crypto::MuxedAccount;
+use stellar_base::operations::RevokeSponsorshipOperation;
+use stellar_base::signature::SignerKey;
+use stellar_base::transaction::{Transaction, TransactionEnvelope};
+use stellar_base::{Asset, Memo, Operation, PublicKey};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct ExplainCommand {
+    #[structopt(
+        name = "XDR",
+        help = "The transaction envelope, base64 encoded or a file containing it"
+    )]
+    pub xdr: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TransactionExplanation {
+    pub transaction: Vec<String>,
+    pub operations: Vec<String>,
+}
+
+/// Describes transactions in plain language, naming the accounts found in
+/// the config.
+struct Explainer<'a> {
+    config: &'a AppConfig,
+    memo: Option<String>,
+}
+
+pub async fn run_explain(
+    out: &mut Output,
+    config: &AppConfig,
+    command: ExplainCommand,
+) -> Result<()> {
+    let envelope = read_envelope(&command.xdr)?;
+    out.print(explain_envelope(config, &envelope))
+        .map_err(Error::Convey)?;
+    Ok(())
+}
+
+/// Returns the plain language explanation of `envelope`.
+pub fn explain_envelope(
+    config: &AppConfig,
+    envelope: &TransactionEnvelope,
+) -> TransactionExplanation {
+    let mut lines = Vec::new();
+    let tx = match envelope {
+        TransactionEnvelope::Transaction(tx) => tx,
+        TransactionEnvelope::FeeBumpTransaction(fee_bump) => {
+            let explainer = Explainer { config, memo: None };
+            lines.push(format!(
+                "{} pays a fee of up to {} XLM for the inner transaction",
+                explainer.muxed_account(fee_bump.fee_source()),
+                format_stroops(fee_bump.fee().to_i64())
+            ));
+            fee_bump.inner_transaction()
+        }
+    };
+    let explainer = Explainer {
+        config,
+        memo: describe_memo(tx.memo()),
+    };
+    explainer.explain_transaction(tx, lines)
+}
+
+impl<'a> Explainer<'a> {
+    fn explain_transaction(
+        &self,
+        tx: &Transaction,
+        mut lines: Vec<String>,
+    ) -> TransactionExplanation {
+        let source = muxed_public_key(tx.source_account());
+        lines.push(format!(
+            "{} submits a transaction with sequence number {} and a fee of up to {} XLM",
+            self.account(&source),
+            tx.sequence(),
+            format_stroops(tx.fee().to_i64())
+        ));
+        if let Some(memo) = &self.memo {
+            lines.push(format!("The transaction has memo {}", memo));
+        }
+        let (lower, upper) = match tx.time_bounds() {
+            Some(time_bounds) => (*time_bounds.lower(), *time_bounds.upper()),
+            None => (None, None),
+        };
+        lines.push(match (lower, upper) {
+            (Some(lower), Some(upper)) => format!("It is valid from {} until {}", lower, upper),
+            (Some(lower), None) => format!("It is valid from {}", lower),
+            (None, Some(upper)) => format!("It is valid until {}", upper),
+            (None, None) => "It never expires".to_string(),
+        });
+        lines.push(format!("It has {} signatures", tx.signatures().len()));
+        let operations = tx
+            .operations()
+            .iter()
+            .map(|operation| {
+                let op_source = match operation.source_account() {
+                    Some(account) => muxed_public_key(account),
+                    None => source.clone(),
+                };
+                self.explain_operation(&op_source, operation)
+            })
+            .collect();
+        TransactionExplanation {
+            transaction: lines,
+            operations,
+        }
+    }
+
+    fn explain_operation(&self, source: &PublicKey, operation: &Operation) -> String {
+        let source_name = self.account(source);
+        match operation {
+            Operation::CreateAccount(op) => format!(
+                "{} creates account {} with {} XLM",
+                source_name,
+                self.account(op.destination()),
+                format_stroops(op.starting_balance().to_i64())
+            ),
+            Operation::Payment(op) => format!(
+                "{} sends {} to {}{}",
+                source_name,
+                self.amount(op.amount(), op.asset()),
+                self.muxed_account(op.destination()),
+                self.with_memo()
+            ),
+            Operation::PathPaymentStrictReceive(op) => format!(
+                "{} sends at most {} to {}, who receives {}{}",
+                source_name,
+                self.amount(op.send_max(), op.send_asset()),
+                self.muxed_account(op.destination()),
+                self.amount(op.destination_amount(), op.destination_asset()),
+                self.with_memo()
+            ),
+            Operation::PathPaymentStrictSend(op) => format!(
+                "{} sends {} to {}, who receives at least {}{}",
+                source_name,
+                self.amount(op.send_amount(), op.send_asset()),
+                self.muxed_account(op.destination()),
+                self.amount(op.destination_min(), op.destination_asset()),
+                self.with_memo()
+            ),
+            Operation::ManageSellOffer(op) => format!(
+                "{} {}",
+                source_name,
+                self.explain_offer(
+                    "sell",
+                    op.offer_id(),
+                    op.amount(),
+                    op.selling(),
+                    op.buying(),
+                    op.price(),
+                )
+            ),
+            Operation::ManageBuyOffer(op) => format!(
+                "{} {}",
+                source_name,
+                self.explain_offer(
+                    "buy",
+                    op.offer_id(),
+                    op.buy_amount(),
+                    op.buying(),
+                    op.selling(),
+                    op.price(),
+                )
+            ),
+            Operation::CreatePassiveSellOffer(op) => format!(
+                "{} offers passively to sell {} for {} at {} each",
+                source_name,
+                self.amount(op.amount(), op.selling()),
+                self.asset(op.buying()),
+                format_price(op.price())
+            ),
+            Operation::SetOptions(op) => {
+                let mut changes = Vec::new();
+                if let Some(destination) = op.inflation_destination() {
+                    changes.push(format!(
+                        "inflation destination {}",
+                        self.account(destination)
+                    ));
+                }
+                if let Some(flags) = op.set_flags() {
+                    changes.push(format!("sets flags {}", format_account_flags(flags)));
+                }
+                if let Some(flags) = op.clear_flags() {
+                    changes.push(format!("clears flags {}", format_account_flags(flags)));
+                }
+                if let Some(weight) = op.master_weight() {
+                    changes.push(format!("master key weight {}", weight));
+                }
+                if let Some(threshold) = op.low_threshold() {
+                    changes.push(format!("low threshold {}", threshold));
+                }
+                if let Some(threshold) = op.medium_threshold() {
+                    changes.push(format!("medium threshold {}", threshold));
+                }
+                if let Some(threshold) = op.high_threshold() {
+                    changes.push(format!("high threshold {}", threshold));
+                }
+                if let Some(home_domain) = op.home_domain() {
+                    changes.push(format!("home domain '{}'", home_domain));
+                }
+                if let Some(signer) = op.signer() {
+                    let key = match signer.key() {
+                        SignerKey::Ed25519(public_key) => self.account(public_key),
+                        key => format_signer_key(key),
+                    };
+                    if *signer.weight() == 0 {
+                        changes.push(format!("removes signer {}", key));
+                    } else {
+                        changes.push(format!("signer {} with weight {}", key, signer.weight()));
+                    }
+                }
+                if changes.is_empty() {
+                    format!("{} sets no account options", source_name)
+                } else {
+                    format!(
+                        "{} sets account options: {}",
+                        source_name,
+                        changes.join(", ")
+                    )
+                }
+            }
+            Operation::ChangeTrust(op) => match op.limit() {
+                // A missing limit is a limit of zero, which removes the trustline.
+                None => format!(
+                    "{} removes its trustline to {}",
+                    source_name,
+                    self.asset(op.asset())
+                ),
+                Some(limit) if *limit == Stroops::max() => {
+                    format!("{} trusts {}", source_name, self.asset(op.asset()))
+                }
+                Some(limit) => format!(
+                    "{} trusts {} up to {}",
+                    source_name,
+                    self.asset(op.asset()),
+                    format_stroops(limit.to_i64())
+                ),
+            },
+            Operation::AllowTrust(op) => {
+                let code = match op.asset() {
+                    CreditAssetType::CreditAlphaNum4(code)
+                    | CreditAssetType::CreditAlphaNum12(code) => code,
+                };
+                let trustor = self.account(op.trustor());
+                let flags = op.authorize_flags();
+                if flags.contains(TrustLineFlags::AUTHORIZED) {
+                    format!(
+                        "{} authorizes {} to hold its {}",
+                        source_name, trustor, code
+                    )
+                } else if flags.contains(TrustLineFlags::AUTHORIZED_TO_MAINTAIN_LIABILITIES) {
+                    format!(
+                        "{} authorizes {} to maintain its offers of {}",
+                        source_name, trustor, code
+                    )
+                } else {
+                    format!(
+                        "{} revokes the authorization of {} to hold its {}",
+                        source_name, trustor, code
+                    )
+                }
+            }
+            Operation::AccountMerge(op) => format!(
+                "{} merges its account into {}, which receives all its XLM{}",
+                source_name,
+                self.muxed_account(op.destination()),
+                self.with_memo()
+            ),
+            Operation::Inflation(_) => format!("{} runs inflation", source_name),
+            Operation::ManageData(op) => match op.data_value() {
+                Some(value) => format!(
+                    "{} sets data entry '{}' to {}",
+                    source_name,
+                    op.data_name(),
+                    format_data_value(value.as_bytes())
+                ),
+                None => format!("{} deletes data entry '{}'", source_name, op.data_name()),
+            },
+            Operation::BumpSequence(op) => format!(
+                "{} bumps its sequence number to {}",
+                source_name,
+                op.bump_to()
+            ),
+            Operation::CreateClaimableBalance(op) => format!(
+                "{} creates a claimable balance of {} for {} claimants",
+                source_name,
+                self.amount(op.amount(), op.asset()),
+                op.claimants().len()
+            ),
+            Operation::ClaimClaimableBalance(op) => format!(
+                "{} claims claimable balance 00000000{}",
+                source_name,
+                hex::encode(op.balance_id().as_bytes())
+            ),
+            Operation::BeginSponsoringFutureReserves(op) => format!(
+                "{} starts paying the reserves of {}",
+                source_name,
+                self.account(op.sponsored_id())
+            ),
+            Operation::EndSponsoringFutureReserves(_) => {
+                format!("{} ends the sponsorship of its reserves", source_name)
+            }
+            Operation::RevokeSponsorship(op) => match op {
+                RevokeSponsorshipOperation::LedgerEntry(_) => {
+                    format!("{} revokes its sponsorship of a ledger entry", source_name)
+                }
+                RevokeSponsorshipOperation::Signer(_) => {
+                    format!("{} revokes its sponsorship of a signer", source_name)
+                }
+            },
+        }
+    }
+
+    fn explain_offer(
+        &self,
+        side: &str,
+        offer_id: &Option<i64>,
+        amount: &Stroops,
+        asset: &Asset,
+        counter_asset: &Asset,
+        price: &Price,
+    ) -> String {
+        let offer_id = offer_id.unwrap_or(0);
+        if offer_id != 0 && amount.to_i64() == 0 {
+            return format!("deletes offer {}", offer_id);
+        }
+        let action = if offer_id == 0 {
+            "offers".to_string()
+        } else {
+            format!("changes offer {}", offer_id)
+        };
+        format!(
+            "{} to {} {} for {} at {} each",
+            action,
+            side,
+            self.amount(amount, asset),
+            self.asset(counter_asset),
+            format_price(price)
+        )
+    }
+
+    /// Returns the config name of `account`, or its account id.
+    fn account(&self, account: &PublicKey) -> String {
+        let account_id = account.account_id();
+        match self
+            .config
+            .accounts
+            .iter()
+            .find(|a| a.account_id == account_id)
+            .and_then(|a| a.name.clone())
+        {
+            Some(name) => name,
+            None => account_id,
+        }
+    }
+
+    fn muxed_account(&self, account: &MuxedAccount) -> String {
+        self.account(&muxed_public_key(account))
+    }
+
+    /// Returns the code of `asset` and its issuer, named if it is in the
+    /// config.
+    fn asset(&self, asset: &Asset) -> String {
+        match asset.as_credit() {
+            None => format_asset(asset),
+            Some(credit) => format!(
+                "{} (issuer {})",
+                credit.code(),
+                self.account(credit.issuer())
+            ),
+        }
+    }
+
+    fn amount(&self, amount: &Stroops, asset: &Asset) -> String {
+        format!("{} {}", format_stroops(amount.to_i64()), self.asset(asset))
+    }
+
+    fn with_memo(&self) -> String {
+        match &self.memo {
+            Some(memo) => format!(" with memo {}", memo),
+            None => "".to_string(),
+        }
+    }
+}
+
+fn describe_memo(memo: &Memo) -> Option<String> {
+    if let Some(text) = memo.as_text() {
+        Some(format!("'{}'", text))
+    } else if let Some(id) = memo.as_id() {
+        Some(format!("id {}", id))
+    } else if let Some(hash) = memo.as_hash() {
+        Some(format!("hash {}", hex::encode(hash)))
+    } else {
+        memo.as_return()
+            .map(|hash| format!("return {}", hex::encode(hash)))
+    }
+}
+
+/// Formats `price` as an exact decimal, or as a fraction if it has too many
+/// or infinite decimals.
+fn format_price(price: &Price) -> String {
+    let numerator = i128::from(price.numerator());
+    let denominator = i128::from(price.denominator());
+    let fraction = || format!("{}/{}", price.numerator(), price.denominator());
+    if denominator <= 0 {
+        return fraction();
+    }
+    // The decimals end if the denominator only has the factors 2 and 5 of
+    // the base, and then they are as many as the largest of their powers.
+    let mut rest = denominator;
+    let (mut twos, mut fives) = (0, 0);
+    while rest % 2 == 0 {
+        rest /= 2;
+        twos += 1;
+    }
+    while rest % 5 == 0 {
+        rest /= 5;
+        fives += 1;
+    }
+    let decimals = twos.max(fives);
+    if rest != 1 || decimals > 20 {
+        return fraction();
+    }
+    let scaled = numerator * 10i128.pow(decimals) / denominator;
+    let scale = 10i128.pow(decimals);
+    let integer = scaled / scale;
+    let decimal = format!("{:0width$}", scaled % scale, width = decimals as usize);
+    let decimal = decimal.trim_end_matches('0');
+    if decimal.is_empty() {
+        integer.to_string()
+    } else {
+        format!("{}.{}", integer, decimal)
+    }
+}
+
+fn format_account_flags(flags: &AccountFlags) -> String {
+    let mut names = Vec::new();
+    if flags.contains(AccountFlags::AUTH_REQUIRED) {
+        names.push("auth_required");
+    }
+    if flags.contains(AccountFlags::AUTH_REVOCABLE) {
+        names.push("auth_revocable");
+    }
+    if flags.contains(AccountFlags::AUTH_IMMUTABLE) {
+        names.push("auth_immutable");
+    }
+    names.join(" ")
+}
+
+/// Formats a data entry value as text if it is printable, otherwise base64
+/// encoded.
+fn format_data_value(value: &[u8]) -> String {
+    match std::str::from_utf8(value) {
+        Ok(text) if !text.chars().any(char::is_control) => format!("'{}'", text),
+        _ => format!("base64 {}", base64::encode(value)),
+    }
+}
+
+impl Render for TransactionExplanation {
+    fn render_for_humans(
+        &self,
+        mut fmt: &mut convey::human::Formatter,
+    ) -> std::result::Result<(), convey::Error> {
+        for line in &self.transaction {
+            text(line).render_for_humans(&mut fmt)?;
+            newline().render_for_humans(&mut fmt)?;
+        }
+        for (index, operation) in self.operations.iter().enumerate() {
+            text(format!("{}. {}", index + 1, operation)).render_for_humans(&mut fmt)?;
+            newline().render_for_humans(&mut fmt)?;
+        }
+        Ok(())
+    }
+
+    render_json!();
+}
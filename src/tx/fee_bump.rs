@@ -0,0 +1,136 @@
+use crate::config::AppConfig;
+use crate::error::Error;
+use crate::fee;
+use crate::tx::status::signature_requirements;
+use crate::tx::{muxed_public_key, operation_name, read_envelope};
+use crate::wallet::{self, TransactionOptions};
+use anyhow::Result;
+use convey::components::{newline, text};
+use convey::{Output, Render};
+use stellar_base::amount::Stroops;
+use stellar_base::transaction::{FeeBumpTransaction, TransactionEnvelope};
+use stellar_horizon::client::HorizonClient;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct FeeBumpCommand {
+    #[structopt(
+        name = "XDR",
+        help = "The signed inner transaction envelope, base64 encoded or a file containing it"
+    )]
+    pub xdr: String,
+    #[structopt(long, help = "The account paying the fee, starts with G, or its name")]
+    pub fee_source: String,
+    #[structopt(
+        long,
+        help = "The maximum total fee in stroops, defaults to the fee chosen by --fee-strategy"
+    )]
+    pub max_fee: Option<i64>,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InnerTransactionSummary {
+    pub hash: String,
+    pub source: String,
+    pub sequence: i64,
+    pub fee: i64,
+    pub operations: Vec<String>,
+    pub signatures: usize,
+}
+
+pub async fn run_fee_bump<H>(
+    mut out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: FeeBumpCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let inner = match read_envelope(&command.xdr)? {
+        TransactionEnvelope::Transaction(tx) => tx,
+        TransactionEnvelope::FeeBumpTransaction(_) => {
+            return Err(anyhow!("The transaction is already a fee bump transaction"))
+        }
+    };
+    if inner.signatures().is_empty() {
+        return Err(anyhow!("The inner transaction is not signed"));
+    }
+    let keypair = config.find_account(&command.fee_source)?.keypair()?;
+    let network = wallet::network(client).await?;
+
+    let summary = InnerTransactionSummary {
+        hash: hex::encode(inner.hash(&network)?),
+        source: muxed_public_key(inner.source_account()).account_id(),
+        sequence: *inner.sequence(),
+        fee: inner.fee().to_i64(),
+        operations: inner
+            .operations()
+            .iter()
+            .map(|op| operation_name(op).to_string())
+            .collect(),
+        signatures: inner.signatures().len(),
+    };
+    out.print(summary).map_err(Error::Convey)?;
+
+    let missing = signature_requirements(client, &inner, &network)
+        .await?
+        .into_iter()
+        .filter(|r| !r.is_satisfied())
+        .map(|r| r.item)
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        return Err(anyhow!(
+            "The inner transaction is missing signatures for {}",
+            missing.join(", ")
+        ));
+    }
+
+    // A fee bump pays for the inner operations and for itself, at a fee
+    // rate at least as high as the inner transaction one.
+    let operations = inner.operations().len() as i64 + 1;
+    let inner_base_fee = inner.fee().to_i64() / (operations - 1).max(1);
+    let fee = match command.max_fee {
+        Some(fee) => fee,
+        None => {
+            fee::choose_base_fee(out, client, &command.transaction, inner_base_fee).await?
+                * operations
+        }
+    };
+    if fee < inner_base_fee * operations {
+        return Err(anyhow!(
+            "The fee {} is below the minimum fee {} for this transaction",
+            fee,
+            inner_base_fee * operations
+        ));
+    }
+
+    let mut fee_bump = FeeBumpTransaction::new(
+        keypair.public_key().clone().into(),
+        Stroops::new(fee),
+        inner,
+    );
+    fee_bump.sign(&keypair, &network)?;
+    wallet::submit_envelope(
+        &mut out,
+        client,
+        &command.transaction,
+        &fee_bump.into_envelope(),
+    )
+    .await
+}
+
+impl Render for InnerTransactionSummary {
+    render_for_humans!(self -> [
+        text(&self.hash), text("\t"),
+        text(&self.source), text("\t"),
+        text(self.sequence.to_string()), text("\t"),
+        text(self.fee.to_string()), text("\t"),
+        text(self.operations.join(",")), text("\t"),
+        text(format!("{} signatures", self.signatures)), newline(),
+    ]);
+
+    render_json!();
+}
@@ -0,0 +1,81 @@
+use crate::config::AppConfig;
+use crate::error::Error;
+use crate::tx::read_envelope;
+use crate::wallet::{self, TransactionXdr};
+use anyhow::Result;
+use convey::Output;
+use stellar_base::signature::DecoratedSignature;
+use stellar_base::transaction::TransactionEnvelope;
+use stellar_base::xdr::XDRSerialize;
+use stellar_horizon::client::HorizonClient;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct MergeCommand {
+    #[structopt(
+        name = "XDR",
+        required = true,
+        min_values = 2,
+        help = "The transaction envelopes, base64 encoded or files containing them"
+    )]
+    pub xdr: Vec<String>,
+}
+
+pub async fn run_merge<H>(
+    out: &mut Output,
+    _config: &AppConfig,
+    client: &H,
+    command: MergeCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let network = wallet::network(client).await?;
+    let mut envelopes = command.xdr.iter().map(|xdr| read_envelope(xdr));
+    let mut merged = envelopes
+        .next()
+        .ok_or_else(|| anyhow!("No transactions to merge"))??;
+    let hash = merged.hash(&network)?;
+    for (envelope, input) in envelopes.zip(command.xdr.iter().skip(1)) {
+        let envelope = envelope?;
+        if envelope.hash(&network)? != hash {
+            return Err(anyhow!(
+                "Transaction {} is different from {}",
+                input,
+                command.xdr[0]
+            ));
+        }
+        merge_signatures(&mut merged, &envelope);
+    }
+    let xdr = merged.xdr_base64()?;
+    out.print(TransactionXdr { xdr }).map_err(Error::Convey)?;
+    Ok(())
+}
+
+fn merge_signatures(merged: &mut TransactionEnvelope, other: &TransactionEnvelope) {
+    match (merged, other) {
+        (TransactionEnvelope::Transaction(merged), TransactionEnvelope::Transaction(other)) => {
+            add_signatures(merged.signatures_mut(), other.signatures());
+        }
+        (
+            TransactionEnvelope::FeeBumpTransaction(merged),
+            TransactionEnvelope::FeeBumpTransaction(other),
+        ) => {
+            add_signatures(merged.signatures_mut(), other.signatures());
+            add_signatures(
+                merged.inner_transaction_mut().signatures_mut(),
+                other.inner_transaction().signatures(),
+            );
+        }
+        // Envelopes with the same hash always have the same type.
+        _ => {}
+    }
+}
+
+fn add_signatures(signatures: &mut Vec<DecoratedSignature>, others: &[DecoratedSignature]) {
+    for signature in others {
+        if !signatures.contains(signature) {
+            signatures.push(signature.clone());
+        }
+    }
+}
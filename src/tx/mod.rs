@@ -0,0 +1,123 @@
+use crate::config::AppConfig;
+use crate::wallet;
+use crate::xdr::read_input;
+use anyhow::Result;
+use convey::Output;
+use stellar_base::amount::{Amount, Stroops};
+use stellar_base::crypto::MuxedAccount;
+use stellar_base::signature::DecoratedSignature;
+use stellar_base::transaction::TransactionEnvelope;
+use stellar_base::xdr::XDRDeserialize;
+use stellar_base::{Operation, PublicKey};
+use structopt::StructOpt;
+
+mod build;
+pub mod check;
+pub mod explain;
+mod fee_bump;
+mod merge;
+pub mod sign;
+mod status;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Inspect and manipulate transactions")]
+pub struct TxCommand {
+    #[structopt(short, long, global = true, help = "Which Horizon server to use")]
+    pub server: Option<String>,
+    #[structopt(subcommand)]
+    pub command: TxInnerCommand,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum TxInnerCommand {
+    #[structopt(about = "Builds a transaction from a spec file")]
+    Build(build::BuildCommand),
+    #[structopt(about = "Wraps a signed transaction in a fee bump transaction")]
+    FeeBump(fee_bump::FeeBumpCommand),
+    #[structopt(about = "Shows which signatures a transaction has and which it still needs")]
+    Status(status::StatusCommand),
+    #[structopt(about = "Merges the signatures of several copies of the same transaction")]
+    Merge(merge::MergeCommand),
+    #[structopt(about = "Explains what a transaction does in plain language")]
+    Explain(explain::ExplainCommand),
+    #[structopt(about = "Signs a transaction after showing what it does")]
+    Sign(sign::SignCommand),
+    #[structopt(about = "Checks a transaction against the current ledger state")]
+    Check(check::CheckCommand),
+    #[structopt(about = "Checks and submits a signed transaction")]
+    Submit(check::SubmitCommand),
+}
+
+pub async fn run_command(
+    mut out: &mut Output,
+    config: &AppConfig,
+    command: TxCommand,
+) -> Result<()> {
+    let client = wallet::horizon_client(&config, &command.server)?;
+    match command.command {
+        TxInnerCommand::Build(cmd) => build::run_build(&mut out, &config, &client, cmd).await,
+        TxInnerCommand::FeeBump(cmd) => {
+            fee_bump::run_fee_bump(&mut out, &config, &client, cmd).await
+        }
+        TxInnerCommand::Status(cmd) => status::run_status(&mut out, &config, &client, cmd).await,
+        TxInnerCommand::Merge(cmd) => merge::run_merge(&mut out, &config, &client, cmd).await,
+        TxInnerCommand::Explain(cmd) => explain::run_explain(&mut out, &config, cmd).await,
+        TxInnerCommand::Sign(cmd) => sign::run_sign(&mut out, &config, &client, cmd).await,
+        TxInnerCommand::Check(cmd) => check::run_check(&mut out, &config, &client, cmd).await,
+        TxInnerCommand::Submit(cmd) => check::run_submit(&mut out, &config, &client, cmd).await,
+    }
+}
+
+/// Reads a transaction envelope from `input`, either the path of a file
+/// containing the base64 encoded XDR, `-` for stdin or the XDR itself.
+pub fn read_envelope(input: &str) -> Result<TransactionEnvelope> {
+    let xdr = read_input(input)?;
+    TransactionEnvelope::from_xdr_base64(xdr.trim())
+        .map_err(|_| anyhow!("Invalid transaction envelope {}", input))
+}
+
+pub fn envelope_signatures(envelope: &TransactionEnvelope) -> &Vec<DecoratedSignature> {
+    match envelope {
+        TransactionEnvelope::Transaction(tx) => tx.signatures(),
+        TransactionEnvelope::FeeBumpTransaction(tx) => tx.signatures(),
+    }
+}
+
+pub fn muxed_public_key(account: &MuxedAccount) -> PublicKey {
+    match account {
+        MuxedAccount::Ed25519(public_key) => public_key.clone(),
+        MuxedAccount::MuxedEd25519(muxed) => muxed.public_key().clone(),
+    }
+}
+
+/// Formats an amount of stroops in units of the asset.
+pub fn format_stroops(amount: i64) -> String {
+    match Amount::from_stroops(&Stroops::new(amount)) {
+        Ok(amount) => amount.to_string(),
+        Err(_) => amount.to_string(),
+    }
+}
+
+pub fn operation_name(operation: &Operation) -> &'static str {
+    match operation {
+        Operation::CreateAccount(_) => "create_account",
+        Operation::Payment(_) => "payment",
+        Operation::PathPaymentStrictReceive(_) => "path_payment_strict_receive",
+        Operation::ManageSellOffer(_) => "manage_sell_offer",
+        Operation::CreatePassiveSellOffer(_) => "create_passive_sell_offer",
+        Operation::SetOptions(_) => "set_options",
+        Operation::ChangeTrust(_) => "change_trust",
+        Operation::AllowTrust(_) => "allow_trust",
+        Operation::AccountMerge(_) => "account_merge",
+        Operation::Inflation(_) => "inflation",
+        Operation::ManageData(_) => "manage_data",
+        Operation::BumpSequence(_) => "bump_sequence",
+        Operation::ManageBuyOffer(_) => "manage_buy_offer",
+        Operation::PathPaymentStrictSend(_) => "path_payment_strict_send",
+        Operation::CreateClaimableBalance(_) => "create_claimable_balance",
+        Operation::ClaimClaimableBalance(_) => "claim_claimable_balance",
+        Operation::BeginSponsoringFutureReserves(_) => "begin_sponsoring_future_reserves",
+        Operation::EndSponsoringFutureReserves(_) => "end_sponsoring_future_reserves",
+        Operation::RevokeSponsorship(_) => "revoke_sponsorship",
+    }
+}
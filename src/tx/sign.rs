@@ -0,0 +1,83 @@
+use crate::config::AppConfig;
+use crate::error::Error;
+use crate::tx::explain::explain_envelope;
+use crate::tx::read_envelope;
+use crate::wallet::{self, TransactionXdr};
+use anyhow::Result;
+use convey::Output;
+use stellar_base::transaction::TransactionEnvelope;
+use stellar_base::xdr::XDRSerialize;
+use stellar_horizon::client::HorizonClient;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct SignCommand {
+    #[structopt(
+        name = "XDR",
+        help = "The transaction envelope, base64 encoded or a file containing it"
+    )]
+    pub xdr: String,
+    #[structopt(
+        long = "signer",
+        required = true,
+        number_of_values = 1,
+        help = "The account signing the transaction, can be repeated"
+    )]
+    pub signers: Vec<String>,
+    #[structopt(short, long, help = "Sign without asking for confirmation")]
+    pub yes: bool,
+}
+
+pub async fn run_sign<H>(
+    mut out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: SignCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let envelope = read_envelope(&command.xdr)?;
+    let envelope = explain_and_sign(
+        &mut out,
+        config,
+        client,
+        envelope,
+        &command.signers,
+        command.yes,
+    )
+    .await?;
+    let xdr = envelope.xdr_base64()?;
+    out.print(TransactionXdr { xdr }).map_err(Error::Convey)?;
+    Ok(())
+}
+
+/// Shows what `envelope` does and signs it with `signers` once the user
+/// confirms it, or right away if `yes` is set.
+pub async fn explain_and_sign<H>(
+    out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    mut envelope: TransactionEnvelope,
+    signers: &[String],
+    yes: bool,
+) -> Result<TransactionEnvelope>
+where
+    H: HorizonClient,
+{
+    let keypairs = signers
+        .iter()
+        .map(|signer| config.find_account(signer)?.keypair())
+        .collect::<Result<Vec<_>>>()?;
+    out.print(explain_envelope(config, &envelope))
+        .map_err(Error::Convey)?;
+    out.flush().map_err(Error::Convey)?;
+    if !yes && !wallet::confirm("Sign this transaction?")? {
+        return Err(anyhow!("Transaction not signed"));
+    }
+    let network = wallet::network(client).await?;
+    for keypair in &keypairs {
+        envelope.sign(keypair, &network)?;
+    }
+    Ok(envelope)
+}
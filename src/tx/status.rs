@@ -0,0 +1,320 @@
+use crate::config::AppConfig;
+use crate::error::Error;
+use crate::signer::parse_signer_key;
+use crate::tx::{envelope_signatures, muxed_public_key, operation_name, read_envelope};
+use crate::wallet;
+use anyhow::Result;
+use convey::components::{newline, text};
+use convey::{Output, Render};
+use std::collections::HashMap;
+use stellar_base::crypto::hash;
+use stellar_base::signature::{DecoratedSignature, SignatureHint, SignerKey};
+use stellar_base::transaction::{Transaction, TransactionEnvelope};
+use stellar_base::{Network, Operation, PublicKey};
+use stellar_horizon::client::HorizonClient;
+use stellar_horizon::resources;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct StatusCommand {
+    #[structopt(
+        name = "XDR",
+        help = "The transaction envelope, base64 encoded or a file containing it"
+    )]
+    pub xdr: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Threshold {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SignatureRequirement {
+    pub item: String,
+    pub account: String,
+    pub threshold: String,
+    pub required_weight: u32,
+    pub signed_weight: u32,
+    pub missing_signers: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TransactionStatus {
+    pub hash: String,
+    pub fully_signed: bool,
+    pub requirements: Vec<SignatureRequirement>,
+}
+
+/// The accounts signing a transaction, loaded from Horizon once each.
+///
+/// Accounts that do not exist yet, for example created by an earlier
+/// operation, are given the signers of a new account: the master key only.
+struct Signers<'a, H: HorizonClient> {
+    client: &'a H,
+    accounts: HashMap<String, Option<resources::Account>>,
+}
+
+pub async fn run_status<H>(
+    out: &mut Output,
+    _config: &AppConfig,
+    client: &H,
+    command: StatusCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let envelope = read_envelope(&command.xdr)?;
+    let network = wallet::network(client).await?;
+    let hash = envelope.hash(&network)?;
+    let requirements = envelope_requirements(client, &envelope, &network).await?;
+    let status = TransactionStatus {
+        hash: hex::encode(&hash),
+        fully_signed: requirements.iter().all(|r| r.is_satisfied()),
+        requirements,
+    };
+    out.print(status).map_err(Error::Convey)?;
+    Ok(())
+}
+
+/// Returns the signatures `envelope` needs and how many it has, including
+/// the inner transaction ones for fee bump transactions.
+pub async fn envelope_requirements<H>(
+    client: &H,
+    envelope: &TransactionEnvelope,
+    network: &Network,
+) -> Result<Vec<SignatureRequirement>>
+where
+    H: HorizonClient,
+{
+    let mut signers = Signers {
+        client,
+        accounts: HashMap::new(),
+    };
+    let hash = envelope.hash(network)?;
+    let signatures = envelope_signatures(envelope);
+    match envelope {
+        TransactionEnvelope::Transaction(tx) => {
+            transaction_requirements(&mut signers, tx, &hash, signatures).await
+        }
+        TransactionEnvelope::FeeBumpTransaction(fee_bump) => {
+            let fee_source = muxed_public_key(fee_bump.fee_source());
+            let mut requirements = vec![
+                signers
+                    .requirement("fee bump", &fee_source, Threshold::Low, &hash, signatures)
+                    .await?,
+            ];
+            let inner = fee_bump.inner_transaction();
+            let inner_hash = inner.hash(network)?;
+            requirements.extend(
+                transaction_requirements(&mut signers, inner, &inner_hash, inner.signatures())
+                    .await?,
+            );
+            Ok(requirements)
+        }
+    }
+}
+
+/// Returns the signatures `tx` needs and how many it has.
+pub async fn signature_requirements<H>(
+    client: &H,
+    tx: &Transaction,
+    network: &Network,
+) -> Result<Vec<SignatureRequirement>>
+where
+    H: HorizonClient,
+{
+    let mut signers = Signers {
+        client,
+        accounts: HashMap::new(),
+    };
+    let hash = tx.hash(network)?;
+    transaction_requirements(&mut signers, tx, &hash, tx.signatures()).await
+}
+
+async fn transaction_requirements<H>(
+    signers: &mut Signers<'_, H>,
+    tx: &Transaction,
+    hash: &[u8],
+    signatures: &[DecoratedSignature],
+) -> Result<Vec<SignatureRequirement>>
+where
+    H: HorizonClient,
+{
+    let source = muxed_public_key(tx.source_account());
+    let mut requirements = vec![
+        signers
+            .requirement("transaction", &source, Threshold::Low, hash, signatures)
+            .await?,
+    ];
+    for (index, operation) in tx.operations().iter().enumerate() {
+        let account = match operation.source_account() {
+            Some(account) => muxed_public_key(account),
+            None => source.clone(),
+        };
+        let item = format!("operation {} {}", index, operation_name(operation));
+        let threshold = operation_threshold(operation);
+        requirements.push(
+            signers
+                .requirement(&item, &account, threshold, hash, signatures)
+                .await?,
+        );
+    }
+    Ok(requirements)
+}
+
+/// Returns the threshold category of `operation`.
+pub fn operation_threshold(operation: &Operation) -> Threshold {
+    match operation {
+        Operation::AllowTrust(_)
+        | Operation::BumpSequence(_)
+        | Operation::ClaimClaimableBalance(_) => Threshold::Low,
+        Operation::AccountMerge(_) => Threshold::High,
+        Operation::SetOptions(op) => {
+            let changes_signers = op.signer().is_some()
+                || op.master_weight().is_some()
+                || op.low_threshold().is_some()
+                || op.medium_threshold().is_some()
+                || op.high_threshold().is_some();
+            if changes_signers {
+                Threshold::High
+            } else {
+                Threshold::Medium
+            }
+        }
+        _ => Threshold::Medium,
+    }
+}
+
+impl<'a, H> Signers<'a, H>
+where
+    H: HorizonClient,
+{
+    async fn requirement(
+        &mut self,
+        item: &str,
+        account_id: &PublicKey,
+        threshold: Threshold,
+        hash: &[u8],
+        signatures: &[DecoratedSignature],
+    ) -> Result<SignatureRequirement> {
+        let key = account_id.account_id();
+        if !self.accounts.contains_key(&key) {
+            let account = wallet::load_account_if_exists(self.client, account_id).await?;
+            self.accounts.insert(key.clone(), account);
+        }
+        let (required_weight, signers) = match &self.accounts[&key] {
+            Some(account) => {
+                let thresholds = &account.thresholds;
+                let required_weight = match threshold {
+                    Threshold::Low => thresholds.low_threshold,
+                    Threshold::Medium => thresholds.medium_threshold,
+                    Threshold::High => thresholds.high_threshold,
+                };
+                let signers = account
+                    .signers
+                    .iter()
+                    .filter(|s| s.weight > 0)
+                    .map(|s| (s.key.clone(), s.weight as u32))
+                    .collect();
+                (required_weight, signers)
+            }
+            None => (0, vec![(key.clone(), 1)]),
+        };
+        let mut signed_weight = 0;
+        let mut missing_signers = Vec::new();
+        for (signer, weight) in signers {
+            if has_signed(&signer, hash, signatures) {
+                signed_weight += weight;
+            } else {
+                missing_signers.push(signer);
+            }
+        }
+        Ok(SignatureRequirement {
+            item: item.to_string(),
+            account: key,
+            threshold: threshold.to_string(),
+            // A threshold of zero still needs a signature.
+            required_weight: u32::from(required_weight).max(1),
+            signed_weight,
+            missing_signers,
+        })
+    }
+}
+
+fn has_signed(signer: &str, tx_hash: &[u8], signatures: &[DecoratedSignature]) -> bool {
+    match parse_signer_key(signer) {
+        Ok(SignerKey::Ed25519(public_key)) => {
+            let hint = SignatureHint::from_public_key(&public_key);
+            signatures
+                .iter()
+                .any(|s| *s.hint() == hint && s.signature().verify(&public_key, tx_hash))
+        }
+        Ok(SignerKey::PreAuthTx(pre_auth)) => pre_auth.as_bytes() == tx_hash,
+        Ok(SignerKey::HashX(hashx)) => signatures
+            .iter()
+            .any(|s| hash(s.signature().as_bytes()) == hashx.as_bytes()),
+        Err(_) => false,
+    }
+}
+
+impl SignatureRequirement {
+    pub fn is_satisfied(&self) -> bool {
+        self.signed_weight >= self.required_weight
+    }
+
+    fn status(&self) -> String {
+        if self.is_satisfied() {
+            "signed".to_string()
+        } else {
+            format!("needs {}", self.missing_signers.join(", "))
+        }
+    }
+}
+
+impl std::fmt::Display for Threshold {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Threshold::Low => write!(f, "low"),
+            Threshold::Medium => write!(f, "medium"),
+            Threshold::High => write!(f, "high"),
+        }
+    }
+}
+
+impl Render for SignatureRequirement {
+    render_for_humans!(self -> [
+        text(&self.item), text("\t"),
+        text(&self.account), text("\t"),
+        text(&self.threshold), text("\t"),
+        text(format!("{}/{}", self.signed_weight, self.required_weight)), text("\t"),
+        text(self.status()), newline(),
+    ]);
+
+    render_json!();
+}
+
+impl Render for TransactionStatus {
+    fn render_for_humans(
+        &self,
+        mut fmt: &mut convey::human::Formatter,
+    ) -> std::result::Result<(), convey::Error> {
+        let status = if self.fully_signed {
+            "fully signed"
+        } else {
+            "not fully signed"
+        };
+        text(&self.hash).render_for_humans(&mut fmt)?;
+        text("\t").render_for_humans(&mut fmt)?;
+        text(status).render_for_humans(&mut fmt)?;
+        newline().render_for_humans(&mut fmt)?;
+        for requirement in &self.requirements {
+            requirement.render_for_humans(&mut fmt)?;
+        }
+        Ok(())
+    }
+
+    render_json!();
+}
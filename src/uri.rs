@@ -0,0 +1,581 @@
+use crate::asset::parse_asset;
+use crate::config::AppConfig;
+use crate::error::Error;
+use crate::fee;
+use crate::http::{self, RequestBody};
+use crate::render::ResponseRender;
+use crate::stellar_toml;
+use crate::tx::check::check_and_submit;
+use crate::tx::read_envelope;
+use crate::tx::sign::explain_and_sign;
+use crate::wallet::{self, TransactionOptions, TransactionXdr};
+use anyhow::Result;
+use convey::components::{newline, text};
+use convey::{Output, Render};
+use hyper::Method;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::str::FromStr;
+use stellar_base::amount::Amount;
+use stellar_base::signature::Signature;
+use stellar_base::transaction::TransactionEnvelope;
+use stellar_base::xdr::{XDRDeserialize, XDRSerialize};
+use stellar_base::{Asset, Network, Operation, PublicKey};
+use stellar_horizon::client::HorizonClient;
+use structopt::StructOpt;
+
+pub const URI_SCHEME: &str = "web+stellar:";
+
+/// Prepended to the URI when signing it, as described in SEP-7.
+const SIGNATURE_PREFIX: &str = "stellar.sep.7 - URI Scheme";
+
+/// Characters escaped in URI parameters, everything but the unreserved ones.
+const PARAM: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Create and open SEP-7 payment and transaction links")]
+pub struct UriCommand {
+    #[structopt(short, long, global = true, help = "Which Horizon server to use")]
+    pub server: Option<String>,
+    #[structopt(subcommand)]
+    pub command: UriInnerCommand,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum UriInnerCommand {
+    #[structopt(about = "Creates a link requesting a payment")]
+    Pay(PayUriCommand),
+    #[structopt(about = "Creates a link requesting to sign a transaction")]
+    Tx(TxUriCommand),
+    #[structopt(about = "Verifies, signs and submits the request of a link")]
+    Open(OpenCommand),
+}
+
+#[derive(Debug, StructOpt)]
+pub struct PayUriCommand {
+    #[structopt(long, help = "The payment destination, starts with G, or its name")]
+    pub to: String,
+    #[structopt(long, help = "The amount to pay, left to the payer if missing")]
+    pub amount: Option<String>,
+    #[structopt(
+        long,
+        default_value = "XLM",
+        help = "The asset to pay, CODE:ISSUER or XLM"
+    )]
+    pub asset: String,
+    #[structopt(long, help = "The memo the payment must have")]
+    pub memo: Option<String>,
+    #[structopt(
+        long,
+        default_value = "text",
+        possible_values = &["text", "id", "hash", "return"],
+        help = "The type of the memo, hash and return memos are base64 encoded"
+    )]
+    pub memo_type: String,
+    #[structopt(flatten)]
+    pub uri: UriOptions,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct TxUriCommand {
+    #[structopt(
+        name = "XDR",
+        help = "The transaction envelope, base64 encoded or a file containing it"
+    )]
+    pub xdr: String,
+    #[structopt(long, help = "The account expected to sign the transaction")]
+    pub pubkey: Option<String>,
+    #[structopt(flatten)]
+    pub uri: UriOptions,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct UriOptions {
+    #[structopt(long, help = "A message shown to the user opening the link")]
+    pub msg: Option<String>,
+    #[structopt(
+        long,
+        help = "The url receiving the signed transaction instead of Horizon"
+    )]
+    pub callback: Option<String>,
+    #[structopt(long, help = "The domain creating the link")]
+    pub origin_domain: Option<String>,
+    #[structopt(
+        long,
+        requires = "origin-domain",
+        help = "The account signing the link, its key must be the URI_REQUEST_SIGNING_KEY of the origin domain"
+    )]
+    pub sign_with: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct OpenCommand {
+    #[structopt(name = "URI", help = "The web+stellar link")]
+    pub uri: String,
+    #[structopt(
+        long = "signer",
+        number_of_values = 1,
+        help = "The account signing the transaction, can be repeated. The first one pays payment requests. Defaults to the pubkey of the link"
+    )]
+    pub signers: Vec<String>,
+    #[structopt(
+        long,
+        help = "The amount to pay, required if the payment link leaves it to the payer"
+    )]
+    pub amount: Option<String>,
+    #[structopt(short, long, help = "Sign without asking for confirmation")]
+    pub yes: bool,
+    #[structopt(
+        long,
+        help = "Send even if the destination requires a memo and there is none"
+    )]
+    pub skip_memo_check: bool,
+    #[structopt(flatten)]
+    pub transaction: TransactionOptions,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Sep7Uri {
+    pub uri: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Sep7Request {
+    pub operation: String,
+    pub origin_domain: Option<String>,
+    pub msg: Option<String>,
+    pub callback: Option<String>,
+}
+
+/// A parsed web+stellar link.
+struct ParsedUri {
+    operation: String,
+    params: Vec<(String, String)>,
+    /// The link without its signature, which is always the last parameter.
+    unsigned: String,
+    signature: Option<String>,
+}
+
+pub async fn run_command(
+    mut out: &mut Output,
+    config: &AppConfig,
+    command: UriCommand,
+) -> Result<()> {
+    let client = wallet::horizon_client(&config, &command.server)?;
+    match command.command {
+        UriInnerCommand::Pay(cmd) => run_pay(&mut out, &config, &client, cmd).await,
+        UriInnerCommand::Tx(cmd) => run_tx(&mut out, &config, &client, cmd).await,
+        UriInnerCommand::Open(cmd) => run_open(&mut out, &config, &client, cmd).await,
+    }
+}
+
+async fn run_pay<H>(
+    out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: PayUriCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let destination = config.resolve_public_key(&command.to)?;
+    let mut params = vec![("destination", destination.account_id())];
+    if let Some(amount) = &command.amount {
+        Amount::from_str(amount)?;
+        params.push(("amount", amount.clone()));
+    }
+    if let Some(credit) = parse_asset(&command.asset)?.as_credit() {
+        params.push(("asset_code", credit.code().to_string()));
+        params.push(("asset_issuer", credit.issuer().account_id()));
+    }
+    if let Some(memo) = &command.memo {
+        let memo_type = format!("MEMO_{}", command.memo_type.to_ascii_uppercase());
+        wallet::parse_memo(memo, &memo_type)?;
+        params.push(("memo", memo.clone()));
+        params.push(("memo_type", memo_type));
+    }
+    let uri = build_uri(config, client, "pay", params, &command.uri).await?;
+    out.print(Sep7Uri { uri }).map_err(Error::Convey)?;
+    Ok(())
+}
+
+async fn run_tx<H>(
+    out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: TxUriCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let envelope = read_envelope(&command.xdr)?;
+    let mut params = vec![("xdr", envelope.xdr_base64()?)];
+    if let Some(pubkey) = &command.pubkey {
+        params.push(("pubkey", config.resolve_public_key(pubkey)?.account_id()));
+    }
+    let uri = build_uri(config, client, "tx", params, &command.uri).await?;
+    out.print(Sep7Uri { uri }).map_err(Error::Convey)?;
+    Ok(())
+}
+
+async fn run_open<H>(
+    mut out: &mut Output,
+    config: &AppConfig,
+    client: &H,
+    command: OpenCommand,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let uri = parse_uri(&command.uri)?;
+    let pubkey = match uri.param("pubkey") {
+        None => None,
+        Some(pubkey) => Some(
+            PublicKey::from_account_id(pubkey)
+                .map_err(|_| anyhow!("Invalid pubkey {} in the link", pubkey))?,
+        ),
+    };
+    let signers = link_signers(config, &command.signers, pubkey.as_ref())?;
+    let origin_domain = uri.param("origin_domain").map(str::to_string);
+    if let Some(domain) = &origin_domain {
+        check_origin_domain(domain)?;
+        verify_signature(&uri, domain).await?;
+    }
+    let callback = match uri.param("callback") {
+        None => None,
+        Some(callback) => Some(
+            callback
+                .strip_prefix("url:")
+                .ok_or_else(|| anyhow!("Invalid callback {}", callback))?
+                .to_string(),
+        ),
+    };
+    let request = Sep7Request {
+        operation: uri.operation.clone(),
+        origin_domain,
+        msg: uri.param("msg").map(str::to_string),
+        callback: callback.clone(),
+    };
+    out.print(request).map_err(Error::Convey)?;
+
+    let network = wallet::network(client).await?;
+    let public = Network::new_public();
+    let passphrase = uri
+        .param("network_passphrase")
+        .unwrap_or_else(|| public.passphrase());
+    if passphrase != network.passphrase() {
+        return Err(anyhow!(
+            "The link is for the network '{}' but the server is on '{}'",
+            passphrase,
+            network.passphrase()
+        ));
+    }
+
+    let envelope = match uri.operation.as_str() {
+        "tx" => {
+            if uri.param("replace").is_some() {
+                return Err(anyhow!(
+                    "Links replacing transaction fields are not supported"
+                ));
+            }
+            let xdr = uri
+                .param("xdr")
+                .ok_or_else(|| anyhow!("The link has no xdr"))?;
+            TransactionEnvelope::from_xdr_base64(xdr)
+                .map_err(|_| anyhow!("Invalid transaction envelope in the link"))?
+        }
+        _ => {
+            let payer = config.resolve_public_key(&signers[0])?;
+            let operation = payment_operation(&uri, command.amount.as_deref())?;
+            let mut tx = wallet::build_transaction(client, &payer, vec![operation]).await?;
+            if let Some(memo) = uri.param("memo") {
+                *tx.memo_mut() =
+                    wallet::parse_memo(memo, uri.param("memo_type").unwrap_or("MEMO_TEXT"))?;
+            }
+            if !command.skip_memo_check {
+                wallet::check_memo_required(client, &tx).await?;
+            }
+            fee::apply_fee_strategy(out, client, &command.transaction, &mut tx).await?;
+            tx.into_envelope()
+        }
+    };
+
+    let envelope =
+        explain_and_sign(&mut out, config, client, envelope, &signers, command.yes).await?;
+    if command.transaction.no_submit {
+        let xdr = envelope.xdr_base64()?;
+        out.print(TransactionXdr { xdr }).map_err(Error::Convey)?;
+        return Ok(());
+    }
+    match callback {
+        Some(callback) => {
+            let form = vec![("xdr".to_string(), envelope.xdr_base64()?)];
+            let response =
+                http::send(Method::POST, &callback, None, RequestBody::Form(form)).await?;
+            let response = serde_json::from_slice(&response).unwrap_or_else(|_| {
+                serde_json::Value::String(String::from_utf8_lossy(&response).into_owned())
+            });
+            out.print(ResponseRender(response)).map_err(Error::Convey)?;
+            Ok(())
+        }
+        None => check_and_submit(&mut out, client, &envelope, &network).await,
+    }
+}
+
+/// Builds a link for `operation` with `params` and the options shared by
+/// all the links, signing it if requested.
+async fn build_uri<H>(
+    config: &AppConfig,
+    client: &H,
+    operation: &str,
+    mut params: Vec<(&str, String)>,
+    options: &UriOptions,
+) -> Result<String>
+where
+    H: HorizonClient,
+{
+    if let Some(callback) = &options.callback {
+        params.push(("callback", format!("url:{}", callback)));
+    }
+    if let Some(msg) = &options.msg {
+        params.push(("msg", msg.clone()));
+    }
+    let network = wallet::network(client).await?;
+    if network.passphrase() != Network::new_public().passphrase() {
+        params.push(("network_passphrase", network.passphrase().to_string()));
+    }
+    if let Some(domain) = &options.origin_domain {
+        check_origin_domain(domain)?;
+        params.push(("origin_domain", domain.clone()));
+    }
+    let query = params
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, utf8_percent_encode(value, PARAM)))
+        .collect::<Vec<_>>()
+        .join("&");
+    let uri = format!("{}{}?{}", URI_SCHEME, operation, query);
+    match &options.sign_with {
+        None => Ok(uri),
+        Some(account) => {
+            let keypair = config.find_account(account)?.keypair()?;
+            let signature = base64::encode(keypair.sign(&signature_payload(&uri)).as_bytes());
+            Ok(format!(
+                "{}&signature={}",
+                uri,
+                utf8_percent_encode(&signature, PARAM)
+            ))
+        }
+    }
+}
+
+fn parse_uri(uri: &str) -> Result<ParsedUri> {
+    let invalid = || anyhow!("Invalid web+stellar link {}", uri);
+    let rest = uri.strip_prefix(URI_SCHEME).ok_or_else(invalid)?;
+    let (operation, query) = match rest.find('?') {
+        Some(index) => (&rest[..index], &rest[index + 1..]),
+        None => return Err(invalid()),
+    };
+    if operation != "pay" && operation != "tx" {
+        return Err(anyhow!("Unsupported link operation {}", operation));
+    }
+    let (unsigned, signature) = match uri.rfind("&signature=") {
+        Some(index) => {
+            let signature = &uri[index + "&signature=".len()..];
+            let signature = url::form_urlencoded::parse(format!("s={}", signature).as_bytes())
+                .next()
+                .map(|(_, value)| value.into_owned());
+            (uri[..index].to_string(), signature)
+        }
+        None => (uri.to_string(), None),
+    };
+    let params = url::form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect();
+    Ok(ParsedUri {
+        operation: operation.to_string(),
+        params,
+        unsigned,
+        signature,
+    })
+}
+
+/// Returns the accounts signing the transaction of a link, which must include
+/// the `pubkey` the link asks for.
+fn link_signers(
+    config: &AppConfig,
+    signers: &[String],
+    pubkey: Option<&PublicKey>,
+) -> Result<Vec<String>> {
+    let pubkey = match pubkey {
+        Some(pubkey) => pubkey,
+        None if signers.is_empty() => {
+            return Err(anyhow!(
+                "Use --signer to choose the accounts signing the transaction"
+            ))
+        }
+        None => return Ok(signers.to_vec()),
+    };
+    if signers.is_empty() {
+        return Ok(vec![pubkey.account_id()]);
+    }
+    for signer in signers {
+        if config.resolve_public_key(signer)? == *pubkey {
+            return Ok(signers.to_vec());
+        }
+    }
+    Err(anyhow!(
+        "The link asks {} to sign the transaction, add it with --signer",
+        pubkey
+    ))
+}
+
+/// Checks that `domain` is a bare domain name, so that its stellar.toml is
+/// fetched over https.
+fn check_origin_domain(domain: &str) -> Result<()> {
+    let valid_label = |label: &str| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    };
+    if domain.len() > 253 || !domain.split('.').all(valid_label) {
+        return Err(anyhow!("The origin domain {} is not a domain name", domain));
+    }
+    Ok(())
+}
+
+/// Checks that `uri` is signed with the URI_REQUEST_SIGNING_KEY of `domain`.
+async fn verify_signature(uri: &ParsedUri, domain: &str) -> Result<()> {
+    let signature = uri
+        .signature
+        .as_ref()
+        .ok_or_else(|| anyhow!("The link from {} is not signed", domain))?;
+    let toml = stellar_toml::fetch(domain).await?;
+    let key = toml.uri_request_signing_key(domain)?;
+    let signature = base64::decode(signature)
+        .ok()
+        .and_then(|signature| Signature::from_slice(&signature).ok())
+        .ok_or_else(|| anyhow!("Invalid link signature"))?;
+    if !signature.verify(&key, &signature_payload(&uri.unsigned)) {
+        return Err(anyhow!(
+            "The link signature does not match the URI_REQUEST_SIGNING_KEY of {}",
+            domain
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the data signed for `uri`: 35 zero bytes and 4, followed by the
+/// SEP-7 prefix and the link.
+fn signature_payload(uri: &str) -> Vec<u8> {
+    let mut payload = vec![0; 36];
+    payload[35] = 4;
+    payload.extend_from_slice(SIGNATURE_PREFIX.as_bytes());
+    payload.extend_from_slice(uri.as_bytes());
+    payload
+}
+
+fn payment_operation(uri: &ParsedUri, amount: Option<&str>) -> Result<Operation> {
+    let destination = uri
+        .param("destination")
+        .ok_or_else(|| anyhow!("The payment link has no destination"))?;
+    let amount = match (uri.param("amount"), amount) {
+        (Some(requested), Some(amount)) if requested != amount => {
+            return Err(anyhow!(
+                "The payment link requests {}, it cannot be changed with --amount",
+                requested
+            ))
+        }
+        (Some(requested), _) => requested,
+        (None, Some(amount)) => amount,
+        (None, None) => {
+            return Err(anyhow!(
+                "The payment link leaves the amount to the payer, use --amount"
+            ))
+        }
+    };
+    let asset = match (uri.param("asset_code"), uri.param("asset_issuer")) {
+        (Some(code), Some(issuer)) => Asset::new_credit(code, PublicKey::from_account_id(issuer)?)?,
+        (Some(code), None) if code.eq_ignore_ascii_case("XLM") => Asset::new_native(),
+        (None, None) => Asset::new_native(),
+        _ => return Err(anyhow!("The payment link has an incomplete asset")),
+    };
+    Ok(Operation::new_payment()
+        .with_destination(PublicKey::from_account_id(destination)?)
+        .with_amount(Amount::from_str(amount)?)?
+        .with_asset(asset)
+        .build()?)
+}
+
+impl ParsedUri {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl Render for Sep7Uri {
+    render_for_humans!(self -> [
+        text(&self.uri), newline(),
+    ]);
+
+    render_json!();
+}
+
+impl Render for Sep7Request {
+    fn render_for_humans(
+        &self,
+        mut fmt: &mut convey::human::Formatter,
+    ) -> std::result::Result<(), convey::Error> {
+        let origin = match &self.origin_domain {
+            Some(domain) => format!("Link from {}, signature verified", domain),
+            None => "Link from an unknown origin, it is not signed".to_string(),
+        };
+        text(origin).render_for_humans(&mut fmt)?;
+        newline().render_for_humans(&mut fmt)?;
+        if let Some(msg) = &self.msg {
+            text(format!("Message: {}", msg)).render_for_humans(&mut fmt)?;
+            newline().render_for_humans(&mut fmt)?;
+        }
+        if let Some(callback) = &self.callback {
+            text(format!("The signed transaction is sent to {}", callback))
+                .render_for_humans(&mut fmt)?;
+            newline().render_for_humans(&mut fmt)?;
+        }
+        Ok(())
+    }
+
+    render_json!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_bare_origin_domains() {
+        check_origin_domain("testanchor.stellar.org").unwrap();
+        check_origin_domain("localhost").unwrap();
+    }
+
+    #[test]
+    fn rejects_origin_domains_with_scheme_port_or_path() {
+        for domain in &[
+            "http://testanchor.stellar.org",
+            "https://testanchor.stellar.org",
+            "testanchor.stellar.org:8000",
+            "testanchor.stellar.org/.well-known",
+            "-stellar.org",
+            "stellar..org",
+            "",
+        ] {
+            assert!(check_origin_domain(domain).is_err(), "{}", domain);
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use crate::config::AppConfig;
+use crate::error::Error;
+use crate::fee::{self, FeeStrategy};
+use crate::horizon::execute_and_print_request;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use convey::components::{newline, text};
+use convey::{Output, Render};
+use std::io::{BufRead, BufReader, Write};
+use stellar_base::crypto::MuxedAccount;
+use stellar_base::transaction::{Transaction, TransactionEnvelope, MIN_BASE_FEE};
+use stellar_base::xdr::XDRSerialize;
+use stellar_base::{KeyPair, Memo, Network, Operation, PublicKey};
+use stellar_horizon::api;
+use stellar_horizon::client::{HorizonClient, HorizonHttpClient};
+use stellar_horizon::error::Error as HorizonError;
+use stellar_horizon::horizon_error::HorizonError as HorizonRequestError;
+use stellar_horizon::request::{Order, PageRequest};
+use stellar_horizon::resources;
+use structopt::StructOpt;
+
+pub const MEMO_REQUIRED_DATA_NAME: &str = "config.memo_required";
+
+#[derive(Debug, StructOpt)]
+pub struct TransactionOptions {
+    #[structopt(long, help = "Print the signed transaction instead of submitting it")]
+    pub no_submit: bool,
+    #[structopt(
+        long,
+        help = "How to choose the fee per operation: p10 to p90, p95 and p99 for a percentile of the recent fees, or max:N for N stroops"
+    )]
+    pub fee_strategy: Option<FeeStrategy>,
+    #[structopt(
+        long,
+        help = "The maximum fee per operation in stroops, 10000 by default with --fee-strategy"
+    )]
+    pub fee_cap: Option<i64>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct MemoOptions {
+    #[structopt(long, help = "Attach a text memo", conflicts_with = "memo-id")]
+    pub memo_text: Option<String>,
+    #[structopt(long, help = "Attach an id memo")]
+    pub memo_id: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TransactionXdr {
+    pub xdr: String,
+}
+
+impl Render for TransactionXdr {
+    render_for_humans!(self -> [
+        text(&self.xdr), newline(),
+    ]);
+
+    render_json!();
+}
+
+impl MemoOptions {
+    pub fn to_memo(&self) -> Result<Memo> {
+        match (&self.memo_text, self.memo_id) {
+            (Some(text), _) => Ok(Memo::new_text(text.as_str())?),
+            (None, Some(id)) => Ok(Memo::new_id(id)),
+            (None, None) => Ok(Memo::new_none()),
+        }
+    }
+}
+
+/// Parses a memo of type text, id, hash or return, also accepted with the
+/// MEMO_ prefix used by SEP-7. Hash and return memos are base64 encoded.
+pub fn parse_memo(memo: &str, memo_type: &str) -> Result<Memo> {
+    let decode = || base64::decode(memo).map_err(|_| anyhow!("Invalid memo {}", memo));
+    let memo_type = memo_type.to_ascii_lowercase();
+    match memo_type.trim_start_matches("memo_") {
+        "text" => Ok(Memo::new_text(memo)?),
+        "id" => Ok(Memo::new_id(
+            memo.parse().map_err(|_| anyhow!("Invalid memo {}", memo))?,
+        )),
+        "hash" => Ok(Memo::new_hash(&decode()?)?),
+        "return" => Ok(Memo::new_return(&decode()?)?),
+        _ => Err(anyhow!("Invalid memo type {}", memo_type)),
+    }
+}
+
+/// Asks `question` and reads the yes or no answer from the terminal, since
+/// stdin may hold the input of the command.
+pub fn confirm(question: &str) -> Result<bool> {
+    let mut terminal = match std::fs::File::open("/dev/tty") {
+        Ok(tty) => Box::new(BufReader::new(tty)) as Box<dyn BufRead>,
+        Err(_) if atty::is(atty::Stream::Stdin) => Box::new(BufReader::new(std::io::stdin())),
+        Err(_) => {
+            return Err(anyhow!(
+                "Cannot ask for confirmation without a terminal, use --yes"
+            ))
+        }
+    };
+    eprint!("{} [y/N] ", question);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    terminal.read_line(&mut answer)?;
+    let answer = answer.trim().to_ascii_lowercase();
+    Ok(answer == "y" || answer == "yes")
+}
+
+pub fn horizon_client(config: &AppConfig, server: &Option<String>) -> Result<HorizonHttpClient> {
+    let server_name = server.clone().unwrap_or(config.default_server.clone());
+    config.horizon_client_for_server(&server_name)
+}
+
+pub async fn network<H>(client: &H) -> Result<Network>
+where
+    H: HorizonClient,
+{
+    let (_, root) = client.request(api::root::root()).await?;
+    Ok(Network::new(root.network_passphrase))
+}
+
+pub async fn load_account<H>(client: &H, account: &PublicKey) -> Result<resources::Account>
+where
+    H: HorizonClient,
+{
+    let request = api::accounts::single(account);
+    let (_, response) = client.request(request).await?;
+    Ok(response)
+}
+
+/// Loads `account`, returning `None` if it does not exist.
+pub async fn load_account_if_exists<H>(
+    client: &H,
+    account: &PublicKey,
+) -> Result<Option<resources::Account>>
+where
+    H: HorizonClient,
+{
+    let request = api::accounts::single(account);
+    match client.request(request).await {
+        Ok((_, response)) => Ok(Some(response)),
+        Err(HorizonError::HorizonRequestError(HorizonRequestError::Other(err)))
+            if err.status == 404 =>
+        {
+            Ok(None)
+        }
+        Err(err) => Err(err)?,
+    }
+}
+
+/// Returns true if `account` requires incoming payments to have a memo, as
+/// described in SEP-29.
+pub fn requires_memo(account: &resources::Account) -> bool {
+    // The data entry value is base64 encoded, MQ== is 1.
+    account
+        .data
+        .get(MEMO_REQUIRED_DATA_NAME)
+        .map(String::as_str)
+        == Some("MQ==")
+}
+
+/// Checks that the destinations of the payments, path payments and account
+/// merges of `tx` accept it, since SEP-29 accounts require a memo.
+pub async fn check_memo_required<H>(client: &H, tx: &Transaction) -> Result<()>
+where
+    H: HorizonClient,
+{
+    if !tx.memo().is_none() {
+        return Ok(());
+    }
+    let mut checked = Vec::new();
+    for operation in tx.operations() {
+        let destination = match operation {
+            Operation::Payment(op) => op.destination(),
+            Operation::PathPaymentStrictReceive(op) => op.destination(),
+            Operation::PathPaymentStrictSend(op) => op.destination(),
+            Operation::AccountMerge(op) => op.destination(),
+            _ => continue,
+        };
+        // Muxed accounts already identify the recipient.
+        let destination = match destination {
+            MuxedAccount::Ed25519(public_key) => public_key,
+            MuxedAccount::MuxedEd25519(_) => continue,
+        };
+        if checked.contains(destination) {
+            continue;
+        }
+        if let Some(account) = load_account_if_exists(client, destination).await? {
+            if requires_memo(&account) {
+                return Err(anyhow!(
+                    "Destination {} requires a memo. Use --skip-memo-check to send anyway.",
+                    destination
+                ));
+            }
+        }
+        checked.push(destination.clone());
+    }
+    Ok(())
+}
+
+pub fn next_sequence(account: &resources::Account) -> Result<i64> {
+    let sequence: i64 = account.sequence.parse()?;
+    Ok(sequence + 1)
+}
+
+pub async fn build_transaction<H>(
+    client: &H,
+    source: &PublicKey,
+    operations: Vec<Operation>,
+) -> Result<Transaction>
+where
+    H: HorizonClient,
+{
+    let account = load_account(client, source).await?;
+    build_transaction_with_sequence(source, next_sequence(&account)?, operations)
+}
+
+pub fn build_transaction_with_sequence(
+    source: &PublicKey,
+    sequence: i64,
+    operations: Vec<Operation>,
+) -> Result<Transaction> {
+    let mut builder = Transaction::builder(source.clone(), sequence, MIN_BASE_FEE);
+    for operation in operations {
+        builder = builder.add_operation(operation);
+    }
+    Ok(builder.into_transaction()?)
+}
+
+pub async fn latest_ledger<H>(client: &H) -> Result<resources::Ledger>
+where
+    H: HorizonClient,
+{
+    let request = api::ledgers::all()
+        .with_order(&Order::Descending)
+        .with_limit(1);
+    let (_, response) = client.request(request).await?;
+    response
+        .records
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("No ledger found"))
+}
+
+pub async fn latest_ledger_close_time<H>(client: &H) -> Result<DateTime<Utc>>
+where
+    H: HorizonClient,
+{
+    Ok(latest_ledger(client).await?.closed_at)
+}
+
+pub async fn sign_and_submit<H>(
+    mut out: &mut Output,
+    client: &H,
+    options: &TransactionOptions,
+    mut tx: Transaction,
+    signers: &[KeyPair],
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    let network = network(client).await?;
+    fee::apply_fee_strategy(&mut out, client, options, &mut tx).await?;
+    for signer in signers {
+        tx.sign(signer, &network)?;
+    }
+    submit_envelope(&mut out, client, options, &tx.into_envelope()).await
+}
+
+pub async fn submit_envelope<H>(
+    mut out: &mut Output,
+    client: &H,
+    options: &TransactionOptions,
+    envelope: &TransactionEnvelope,
+) -> Result<()>
+where
+    H: HorizonClient,
+{
+    if options.no_submit {
+        let xdr = envelope.xdr_base64()?;
+        out.print(TransactionXdr { xdr }).map_err(Error::Convey)?;
+        return Ok(());
+    }
+    let request = api::transactions::submit(envelope)?;
+    execute_and_print_request(&mut out, client, request).await
+}
@@ -0,0 +1,203 @@
+use crate::config::AppConfig;
+use crate::error::Error;
+use crate::render::ResponseRender;
+use anyhow::Result;
+use convey::components::{newline, text};
+use convey::{Output, Render};
+use serde_json::Value;
+use std::io::Read;
+use std::path::Path;
+use stellar_base::xdr as stellar_xdr;
+use structopt::StructOpt;
+use xdr_rs_serialize::de::{read_json_string, XDRIn};
+use xdr_rs_serialize::ser::XDROut;
+
+const XDR_TYPES: &[&str] = &[
+    "Asset",
+    "LedgerEntry",
+    "LedgerEntryChanges",
+    "LedgerHeader",
+    "LedgerKey",
+    "Operation",
+    "OperationResult",
+    "Transaction",
+    "TransactionEnvelope",
+    "TransactionMeta",
+    "TransactionResult",
+    "TransactionResultPair",
+];
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Decode and encode Stellar XDR")]
+pub enum XdrCommand {
+    #[structopt(about = "Decodes base64 encoded XDR to JSON")]
+    Decode(DecodeCommand),
+    #[structopt(about = "Encodes JSON to base64 encoded XDR")]
+    Encode(EncodeCommand),
+}
+
+#[derive(Debug, StructOpt)]
+pub struct DecodeCommand {
+    #[structopt(long = "type", possible_values = XDR_TYPES, help = "The XDR type")]
+    pub type_: String,
+    #[structopt(
+        name = "XDR",
+        help = "The base64 encoded XDR, a file containing it or - to read from stdin"
+    )]
+    pub xdr: String,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct EncodeCommand {
+    #[structopt(long = "type", possible_values = XDR_TYPES, help = "The XDR type")]
+    pub type_: String,
+    #[structopt(
+        name = "JSON",
+        help = "The JSON value, a file containing it or - to read from stdin"
+    )]
+    pub json: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EncodedXdr {
+    pub xdr: String,
+}
+
+impl Render for EncodedXdr {
+    render_for_humans!(self -> [
+        text(&self.xdr), newline(),
+    ]);
+
+    render_json!();
+}
+
+pub fn run_command(mut out: &mut Output, _config: &AppConfig, command: XdrCommand) -> Result<()> {
+    match command {
+        XdrCommand::Decode(cmd) => run_decode(&mut out, cmd),
+        XdrCommand::Encode(cmd) => run_encode(&mut out, cmd),
+    }
+}
+
+pub fn run_decode(out: &mut Output, command: DecodeCommand) -> Result<()> {
+    let xdr = read_input(&command.xdr)?;
+    let value = decode_as(&command.type_, xdr.trim())?;
+    out.print(ResponseRender(value)).map_err(Error::Convey)?;
+    Ok(())
+}
+
+pub fn run_encode(out: &mut Output, command: EncodeCommand) -> Result<()> {
+    let json = read_input(&command.json)?;
+    let xdr = encode_as(&command.type_, json)?;
+    out.print(EncodedXdr { xdr }).map_err(Error::Convey)?;
+    Ok(())
+}
+
+/// Reads a command argument that can be given inline, as the path of a file
+/// containing it or as `-` to read it from stdin.
+pub fn read_input(input: &str) -> Result<String> {
+    if input == "-" {
+        let mut content = String::new();
+        std::io::stdin().read_to_string(&mut content)?;
+        return Ok(content);
+    }
+    match std::fs::read_to_string(input) {
+        Ok(content) => Ok(content),
+        // Checking that the path exists also covers inline values too long
+        // to be file names.
+        Err(_) if !Path::new(input).exists() => Ok(input.to_string()),
+        Err(err) => Err(anyhow!("Cannot read {}: {}", input, err)),
+    }
+}
+
+pub fn decode_as(type_: &str, xdr: &str) -> Result<Value> {
+    let xdr = base64::decode(xdr).map_err(|_| anyhow!("Invalid base64 encoded XDR"))?;
+    match type_ {
+        "Asset" => decode::<stellar_xdr::Asset>(&xdr),
+        "LedgerEntry" => decode::<stellar_xdr::LedgerEntry>(&xdr),
+        "LedgerEntryChanges" => decode::<stellar_xdr::LedgerEntryChanges>(&xdr),
+        "LedgerHeader" => decode::<stellar_xdr::LedgerHeader>(&xdr),
+        "LedgerKey" => decode::<stellar_xdr::LedgerKey>(&xdr),
+        "Operation" => decode::<stellar_xdr::Operation>(&xdr),
+        "OperationResult" => decode::<stellar_xdr::OperationResult>(&xdr),
+        "Transaction" => decode::<stellar_xdr::Transaction>(&xdr),
+        "TransactionEnvelope" => decode::<stellar_xdr::TransactionEnvelope>(&xdr),
+        "TransactionMeta" => decode::<stellar_xdr::TransactionMeta>(&xdr),
+        "TransactionResult" => decode::<stellar_xdr::TransactionResult>(&xdr),
+        "TransactionResultPair" => decode::<stellar_xdr::TransactionResultPair>(&xdr),
+        _ => Err(anyhow!("Unsupported XDR type {}", type_)),
+    }
+}
+
+pub fn encode_as(type_: &str, json: String) -> Result<String> {
+    let xdr = match type_ {
+        "Asset" => encode::<stellar_xdr::Asset>(json),
+        "LedgerEntry" => encode::<stellar_xdr::LedgerEntry>(json),
+        "LedgerEntryChanges" => encode::<stellar_xdr::LedgerEntryChanges>(json),
+        "LedgerHeader" => encode::<stellar_xdr::LedgerHeader>(json),
+        "LedgerKey" => encode::<stellar_xdr::LedgerKey>(json),
+        "Operation" => encode::<stellar_xdr::Operation>(json),
+        "OperationResult" => encode::<stellar_xdr::OperationResult>(json),
+        "Transaction" => encode::<stellar_xdr::Transaction>(json),
+        "TransactionEnvelope" => encode::<stellar_xdr::TransactionEnvelope>(json),
+        "TransactionMeta" => encode::<stellar_xdr::TransactionMeta>(json),
+        "TransactionResult" => encode::<stellar_xdr::TransactionResult>(json),
+        "TransactionResultPair" => encode::<stellar_xdr::TransactionResultPair>(json),
+        _ => Err(anyhow!("Unsupported XDR type {}", type_)),
+    }?;
+    Ok(base64::encode(&xdr))
+}
+
+/// Adds the decoded form of the XDR fields of the Horizon transactions in
+/// `value`, for example `envelope` next to `envelope_xdr`.
+///
+/// A field that cannot be decoded, for example meta from a newer protocol, is
+/// replaced by an object with the `error` instead of failing the whole value.
+pub fn decode_transaction_xdr(value: &mut Value) {
+    const FIELDS: &[(&str, &str, &str)] = &[
+        ("envelope_xdr", "envelope", "TransactionEnvelope"),
+        ("result_xdr", "result", "TransactionResult"),
+        ("result_meta_xdr", "result_meta", "TransactionMeta"),
+        ("fee_meta_xdr", "fee_meta", "LedgerEntryChanges"),
+    ];
+    match value {
+        Value::Object(object) => {
+            for field in object.values_mut() {
+                decode_transaction_xdr(field);
+            }
+            for (field, decoded_field, type_) in FIELDS {
+                let decoded = match object.get(*field) {
+                    Some(Value::String(xdr)) => match decode_as(type_, xdr) {
+                        Ok(decoded) => decoded,
+                        Err(err) => serde_json::json!({ "error": err.to_string() }),
+                    },
+                    _ => continue,
+                };
+                object.insert(decoded_field.to_string(), decoded);
+            }
+        }
+        Value::Array(array) => {
+            for item in array.iter_mut() {
+                decode_transaction_xdr(item);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn decode<T: XDRIn + XDROut>(xdr: &[u8]) -> Result<Value> {
+    let (value, _) = T::read_xdr(xdr).map_err(|err| anyhow!("Invalid XDR: {:?}", err))?;
+    let mut json = Vec::new();
+    value
+        .write_json(&mut json)
+        .map_err(|err| anyhow!("Cannot convert XDR to JSON: {:?}", err))?;
+    Ok(serde_json::from_slice(&json)?)
+}
+
+fn encode<T: XDRIn + XDROut>(json: String) -> Result<Vec<u8>> {
+    let value: T = read_json_string(json).map_err(|err| anyhow!("Invalid JSON: {:?}", err))?;
+    let mut xdr = Vec::new();
+    value
+        .write_xdr(&mut xdr)
+        .map_err(|err| anyhow!("Cannot convert JSON to XDR: {:?}", err))?;
+    Ok(xdr)
+}